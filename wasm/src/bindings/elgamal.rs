@@ -0,0 +1,67 @@
+//! Bindings for ElGamal encryption under a BabyJubJub key
+
+use super::{scalar_to_js, scalars_from_js};
+use crate::{
+    elgamal::{self, DecryptionKey, ElGamalCiphertext},
+    errors::UtilsError,
+    helpers::{
+        embedded_scalar_from_hex_string, embedded_scalar_to_hex_string, jubjub_from_hex_string,
+        jubjub_to_hex_string,
+    },
+};
+use wasm_bindgen::prelude::*;
+
+/// Generates a random ElGamal keypair.
+///
+/// # Returns
+///
+/// * A vector of JavaScript values. The first element is the hex-encoded
+///   decryption key, and the second element is the hex-encoded encryption key,
+///   in the same format as the managing cluster key.
+#[wasm_bindgen]
+pub fn generate_encryption_keypair() -> Result<Vec<JsValue>, JsError> {
+    let (decryption_key, encryption_key) = elgamal::generate_keypair()?;
+    Ok(vec![
+        JsValue::from_str(&embedded_scalar_to_hex_string(decryption_key.key())),
+        JsValue::from_str(&jubjub_to_hex_string(&encryption_key)?),
+    ])
+}
+
+/// Encrypts a vector of scalars under a BabyJubJub key.
+///
+/// # Arguments
+///
+/// * `message` - The scalars to encrypt, as hex strings.
+/// * `encryption_key` - The hex-encoded encryption key.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON representation of the ciphertext.
+#[wasm_bindgen]
+pub fn encrypt(message: Vec<JsValue>, encryption_key: &str) -> Result<JsValue, JsError> {
+    let message = scalars_from_js(&message)?;
+    let encryption_key = jubjub_from_hex_string(encryption_key)?;
+    let (ciphertext, _) = elgamal::encrypt(&message, &encryption_key)?;
+    let serialized = serde_json::to_string(&ciphertext)
+        .map_err(|e| UtilsError::Serde(format!("error serializing ciphertext: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
+
+/// Decrypts a ciphertext with a BabyJubJub decryption key.
+///
+/// # Arguments
+///
+/// * `ciphertext_str` - Serialized ciphertext, as returned by `encrypt`.
+/// * `decryption_key` - The hex-encoded decryption key.
+///
+/// # Returns
+///
+/// * A vector of JavaScript values containing the decrypted scalars.
+#[wasm_bindgen]
+pub fn decrypt(ciphertext_str: &str, decryption_key: &str) -> Result<Vec<JsValue>, JsError> {
+    let ciphertext: ElGamalCiphertext = serde_json::from_str(ciphertext_str)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing ciphertext: {e}")))?;
+    let decryption_key = DecryptionKey::from(embedded_scalar_from_hex_string(decryption_key)?);
+    let message = elgamal::decrypt(&ciphertext, &decryption_key)?;
+    Ok(message.iter().map(scalar_to_js).collect())
+}
@@ -0,0 +1,140 @@
+//! Bindings for holding `sk_root` inside the wasm module behind opaque handles
+//!
+//! A handle may be passed to the signing and backup exports in place of
+//! `sk_root` in hex, so that the secret is parsed once and never held in a JS
+//! string. The exports that import or restore `sk_root` return a handle
+
+use crate::{
+    errors::UtilsError,
+    helpers::{bytes_from_hex_string, get_root_key},
+    key_handles::{KeyHandle, KeyHandleStore},
+    keys::{self, Network, PkRootEncoding},
+};
+use ethers::core::k256::ecdsa::SigningKey;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+thread_local! {
+    /// The keys held by the module
+    static KEY_STORE: RefCell<KeyHandleStore> = RefCell::new(KeyHandleStore::new());
+}
+
+/// Get a copy of the key referenced by the given handle
+pub(crate) fn get_key(handle: KeyHandle) -> Result<SigningKey, UtilsError> {
+    KEY_STORE.with(|store| store.borrow().export(handle))
+}
+
+/// Get a copy of the key passed in from JS, either as sk_root in hex or as
+/// the handle of a key held by the module
+///
+/// Returns `None` if the value is neither
+pub(crate) fn key_from_js(value: &JsValue) -> Result<Option<SigningKey>, UtilsError> {
+    if let Some(sk_root) = value.as_string() {
+        let (signing_key, _) = get_root_key(&sk_root)?;
+        return Ok(Some(signing_key));
+    }
+
+    match value.as_f64() {
+        Some(handle) => {
+            let handle = KeyHandle::try_from(handle as u64)
+                .ok()
+                .filter(|h| f64::from(*h) == handle)
+                .ok_or_else(|| {
+                    UtilsError::InvalidKeyHandle(format!("invalid key handle: {handle}"))
+                })?;
+            get_key(handle).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// Get a copy of the key passed in from JS, either as sk_root in hex or as
+/// the handle of a key held by the module
+pub(crate) fn require_key_from_js(value: &JsValue) -> Result<SigningKey, UtilsError> {
+    key_from_js(value)?.ok_or_else(|| {
+        UtilsError::InvalidKeyHandle("expected sk_root in hex or a key handle".to_string())
+    })
+}
+
+/// Insert a key into the store, returning its handle
+pub(crate) fn insert_key(key: SigningKey) -> Result<KeyHandle, UtilsError> {
+    KEY_STORE.with(|store| store.borrow_mut().insert(key))
+}
+
+/// Imports sk_root into the module.
+///
+/// # Arguments
+///
+/// * `sk_root` - sk_root in hex.
+///
+/// # Returns
+/// * The handle referencing sk_root.
+#[wasm_bindgen]
+pub fn import_key(sk_root: &str) -> Result<KeyHandle, JsError> {
+    let (signing_key, _) = get_root_key(sk_root)?;
+    Ok(insert_key(signing_key)?)
+}
+
+/// Derives the sk_root of an account from a signature and holds it in the
+/// module.
+///
+/// # Arguments
+///
+/// * `signature` - The hex-encoded signature over the network's root key message.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+/// * `index` - The index of the account, zero for the default account.
+///
+/// # Returns
+/// * The handle referencing the derived sk_root.
+#[wasm_bindgen]
+pub fn derive_key(signature: &str, network: &str, index: u32) -> Result<KeyHandle, JsError> {
+    let signature = bytes_from_hex_string(signature)?;
+    let network: Network = network.parse()?;
+    let derived = keys::derive_account_key_hierarchy(&signature, network, index)?;
+    Ok(insert_key(derived.sk_root.clone())?)
+}
+
+/// Exports the sk_root referenced by a handle.
+///
+/// The key remains held by the module until released.
+///
+/// # Arguments
+///
+/// * `handle` - The handle referencing sk_root.
+///
+/// # Returns
+/// * sk_root in hex.
+#[wasm_bindgen]
+pub fn export_key(handle: KeyHandle) -> Result<JsValue, JsError> {
+    let signing_key = get_key(handle)?;
+    Ok(JsValue::from_str(&format!(
+        "0x{}",
+        hex::encode(signing_key.to_bytes())
+    )))
+}
+
+/// Gets the pk_root of the sk_root referenced by a handle.
+///
+/// # Arguments
+///
+/// * `handle` - The handle referencing sk_root.
+///
+/// # Returns
+/// * pk_root as hex-encoded uncompressed SEC1 bytes.
+#[wasm_bindgen]
+pub fn get_pk_root_from_key(handle: KeyHandle) -> Result<JsValue, JsError> {
+    let signing_key = get_key(handle)?;
+    let encoded = keys::encode_pk_root(signing_key.verifying_key(), PkRootEncoding::Uncompressed);
+    Ok(JsValue::from_str(&encoded[0]))
+}
+
+/// Releases the sk_root referenced by a handle, zeroizing it.
+///
+/// # Arguments
+///
+/// * `handle` - The handle referencing sk_root.
+#[wasm_bindgen]
+pub fn release_key(handle: KeyHandle) -> Result<(), JsError> {
+    KEY_STORE.with(|store| store.borrow_mut().release(handle))?;
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! Bindings for deriving the key hierarchy of a wallet
+
+use super::{
+    key_handles::{insert_key, require_key_from_js},
+    scalar_to_js,
+};
+use crate::{
+    errors::UtilsError,
+    helpers::{
+        address_to_checksum_hex_string, biguint_to_hex_string, bytes_from_hex_string, get_root_key,
+        jubjub_from_hex_string, pk_root_from_hex_string, scalar_from_hex_string,
+        scalar_to_fixed_hex_string,
+    },
+    key_handles::KeyHandle,
+    keys::{
+        self, compute_key_hierarchy_shares, derive_signing_key, KeyHierarchy, Network,
+        PkRootEncoding,
+    },
+    mnemonic,
+    types::{PublicIdentificationKey, SecretIdentificationKey},
+    wallet::Keychain,
+};
+use num_bigint::BigUint;
+use wasm_bindgen::prelude::*;
+
+// Get sk_root from signature over ROOT_KEY_MESSAGE
+#[wasm_bindgen]
+pub fn derive_signing_key_from_signature(msg: &str) -> Result<JsValue, JsError> {
+    let bytes = bytes_from_hex_string(msg)?;
+    let sk_root = derive_signing_key(&bytes)?;
+    Ok(JsValue::from_str(&hex::encode(sk_root.to_bytes())))
+}
+
+/// Derive sk_root from an Ethereum private key, as the web app derives it
+/// from the key's wallet.
+///
+/// # Arguments
+///
+/// * `eth_key` - The hex-encoded Ethereum private key.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+///
+/// # Returns
+/// * The handle referencing the derived sk_root, which is held in the module.
+#[wasm_bindgen]
+pub fn derive_signing_key_from_eth_key(eth_key: &str, network: &str) -> Result<KeyHandle, JsError> {
+    let (eth_key, _) = get_root_key(eth_key)?;
+    let network: Network = network.parse()?;
+    let keys = keys::derive_key_hierarchy_from_eth_key(&eth_key, network)?;
+    Ok(insert_key(keys.sk_root.clone())?)
+}
+
+/// Sign the root key message with an Ethereum private key, as the key's
+/// wallet does when the web app requests it with `personal_sign`.
+///
+/// # Arguments
+///
+/// * `eth_key` - The hex-encoded Ethereum private key.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+///
+/// # Returns
+/// * The hex-encoded 65-byte `r || s || v` signature.
+#[wasm_bindgen]
+pub fn sign_root_key_message(eth_key: &str, network: &str) -> Result<JsValue, JsError> {
+    let (eth_key, _) = get_root_key(eth_key)?;
+    let network: Network = network.parse()?;
+    let signature = keys::sign_root_key_message(&eth_key, network)?;
+    Ok(JsValue::from_str(&format!(
+        "0x{}",
+        hex::encode(&*signature)
+    )))
+}
+
+/// Get the message a user's Ethereum wallet signs to derive their keys.
+///
+/// # Arguments
+///
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+///
+/// # Returns
+/// * The message to sign.
+#[wasm_bindgen]
+pub fn get_root_key_message(network: &str) -> Result<String, JsError> {
+    let network: Network = network.parse()?;
+    Ok(network.root_key_message())
+}
+
+/// Derive the secret keys and seeds of a wallet from a single signature.
+///
+/// # Arguments
+///
+/// * `signature` - The hex-encoded signature over the network's root key message.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+///
+/// # Returns
+/// * A vector of JavaScript values holding, in hex, `sk_root`, `sk_match`,
+///   the blinder seed and the share seed.
+#[wasm_bindgen]
+pub fn derive_key_hierarchy(signature: &str, network: &str) -> Result<Vec<JsValue>, JsError> {
+    derive_account_key_hierarchy(signature, network, 0 /* index */)
+}
+
+/// Derive the secret keys and seeds of one of several accounts sharing a
+/// single signature.
+///
+/// # Arguments
+///
+/// * `signature` - The hex-encoded signature over the network's root key message.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+/// * `index` - The index of the account, account zero is the account of
+///   `derive_key_hierarchy`.
+///
+/// # Returns
+/// * A vector of JavaScript values holding, in hex, `sk_root`, `sk_match`,
+///   the blinder seed and the share seed.
+#[wasm_bindgen]
+pub fn derive_account_key_hierarchy(
+    signature: &str,
+    network: &str,
+    index: u32,
+) -> Result<Vec<JsValue>, JsError> {
+    let signature = bytes_from_hex_string(signature)?;
+    let network: Network = network.parse()?;
+    let keys = keys::derive_account_key_hierarchy(&signature, network, index)?;
+
+    let to_hex = |scalar| biguint_to_hex_string(&BigUint::from(scalar));
+    Ok(vec![
+        JsValue::from_str(&format!("0x{}", hex::encode(keys.sk_root.to_bytes()))),
+        JsValue::from_str(&to_hex(*keys.sk_match.key())),
+        JsValue::from_str(&to_hex(keys.blinder_seed)),
+        JsValue::from_str(&to_hex(keys.share_seed)),
+    ])
+}
+
+/// Enumerate the wallet IDs of the first accounts derived from a signature.
+///
+/// # Arguments
+///
+/// * `signature` - The hex-encoded signature over the network's root key message.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`.
+/// * `count` - The number of accounts to enumerate, at most 256.
+///
+/// # Returns
+/// * A vector of JavaScript values holding the wallet IDs, in index order.
+#[wasm_bindgen]
+pub fn derive_account_ids(
+    signature: &str,
+    network: &str,
+    count: u32,
+) -> Result<Vec<JsValue>, JsError> {
+    let signature = bytes_from_hex_string(signature)?;
+    let network: Network = network.parse()?;
+    let ids = keys::derive_account_ids(&signature, network, count)?;
+    Ok(ids
+        .iter()
+        .map(|id| JsValue::from_str(&id.to_string()))
+        .collect())
+}
+
+/// Encode sk_root as a BIP-39 mnemonic for backup.
+///
+/// # Arguments
+///
+/// * `sk_root` - sk_root in hex, or the handle of a key held by the module.
+///
+/// # Returns
+/// * The 24 word mnemonic.
+#[wasm_bindgen]
+pub fn sk_root_to_mnemonic(sk_root: &JsValue) -> Result<String, JsError> {
+    let sk_root = require_key_from_js(sk_root)?;
+    Ok(mnemonic::sk_root_to_mnemonic(&sk_root).to_string())
+}
+
+/// Restore sk_root from a BIP-39 mnemonic backup and hold it in the module.
+///
+/// # Arguments
+///
+/// * `mnemonic` - The 24 word mnemonic.
+///
+/// # Returns
+/// * The handle referencing the restored sk_root.
+#[wasm_bindgen]
+pub fn sk_root_from_mnemonic(mnemonic: &str) -> Result<KeyHandle, JsError> {
+    let sk_root = mnemonic::sk_root_from_mnemonic(mnemonic)?;
+    Ok(insert_key(sk_root)?)
+}
+
+/// Derive the ID of the wallet owned by a key hierarchy.
+///
+/// # Arguments
+///
+/// * `key` - Either sk_root in hex, or pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+/// * The wallet ID, a UUID string.
+#[wasm_bindgen]
+pub fn derive_wallet_id(key: &str) -> Result<JsValue, JsError> {
+    // A SEC1 encoded point is tagged by its leading byte, so try it first and
+    // fall back to sk_root, which may be of any width
+    let pk_root = match pk_root_from_hex_string(key) {
+        Ok(pk_root) => pk_root,
+        Err(_) => get_root_key(key)?.1,
+    };
+
+    Ok(JsValue::from_str(
+        &keys::derive_wallet_id(&pk_root).to_string(),
+    ))
+}
+
+/// Get the shares of the key hierarchy computed from `sk_root`
+///
+/// # Arguments
+///
+/// * `sk_root` - The root key to compute the hierarchy from.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`,
+///   defaulting to `testnet`.
+///
+/// # Returns
+/// * String representation of the shares of the key hierarchy.
+#[wasm_bindgen]
+pub fn get_key_hierarchy_shares(
+    sk_root: &str,
+    network: Option<String>,
+) -> Result<Vec<JsValue>, JsError> {
+    let (sk_root, _) = get_root_key(sk_root)?;
+    let shares = compute_key_hierarchy_shares(&sk_root, parse_network(network)?);
+    Ok(shares.iter().map(scalar_to_js).collect())
+}
+
+/// Get the shares of the key hierarchy from its public keys alone, for
+/// clients that do not hold `sk_root`
+///
+/// # Arguments
+///
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+/// * `pk_match` - pk_match in hex.
+///
+/// # Returns
+/// * String representation of the shares of the key hierarchy.
+#[wasm_bindgen]
+pub fn get_public_key_hierarchy_shares(
+    pk_root: &str,
+    pk_match: &str,
+) -> Result<Vec<JsValue>, JsError> {
+    let keychain = Keychain::new(
+        pk_root_from_hex_string(pk_root)?,
+        PublicIdentificationKey::from(scalar_from_hex_string(pk_match)?),
+    );
+    Ok(keychain.pack().iter().map(scalar_to_js).collect())
+}
+
+/// Get the public match key corresponding to a secret match key
+///
+/// # Arguments
+///
+/// * `sk_match` - sk_match in hex.
+///
+/// # Returns
+/// * pk_match as a fixed-width hex string.
+#[wasm_bindgen]
+pub fn get_pk_match(sk_match: &str) -> Result<JsValue, JsError> {
+    let sk_match = SecretIdentificationKey::from(scalar_from_hex_string(sk_match)?);
+    let pk_match = sk_match.get_public_key();
+    Ok(JsValue::from_str(&scalar_to_fixed_hex_string(
+        &pk_match.key,
+    )))
+}
+
+/// Get the string representation of the key hierarchy computed from `sk_root`
+///
+/// # Arguments
+///
+/// * `sk_root` - The root key to compute the hierarchy from.
+/// * `network` - The network to derive keys for, `mainnet` or `testnet`,
+///   defaulting to `testnet`.
+///
+/// # Returns
+/// * JSON representation of the key hierarchy, with `public_keys` and
+///   `private_keys` sections.
+#[wasm_bindgen]
+pub fn get_key_hierarchy(sk_root: &str, network: Option<String>) -> Result<JsValue, JsError> {
+    let (sk_root, _) = get_root_key(sk_root)?;
+    let key_hierarchy = KeyHierarchy::from_sk_root(&sk_root, parse_network(network)?);
+    let serialized = serde_json::to_string(&key_hierarchy)
+        .map_err(|e| UtilsError::Serde(format!("error serializing key hierarchy: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
+
+/// Encode `pk_root` in the requested format.
+///
+/// # Arguments
+///
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+/// * `encoding` - One of `uncompressed`, `compressed` or `words`.
+///
+/// # Returns
+/// * A vector of JavaScript values holding the hex encoding, a single SEC1
+///   string or the four scalar words.
+#[wasm_bindgen]
+pub fn encode_pk_root(pk_root: &str, encoding: &str) -> Result<Vec<JsValue>, JsError> {
+    let pk_root = pk_root_from_hex_string(pk_root)?;
+    let encoding: PkRootEncoding = encoding.parse()?;
+    Ok(keys::encode_pk_root(&pk_root, encoding)
+        .iter()
+        .map(|word| JsValue::from_str(word))
+        .collect())
+}
+
+/// Get the Ethereum address of `pk_root`.
+///
+/// # Arguments
+///
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+/// * A `JsValue` containing the checksummed address.
+#[wasm_bindgen]
+pub fn address_from_pk_root(pk_root: &str) -> Result<JsValue, JsError> {
+    let pk_root = pk_root_from_hex_string(pk_root)?;
+    let address = keys::address_from_pk_root(&pk_root);
+    Ok(JsValue::from_str(&address_to_checksum_hex_string(&address)))
+}
+
+/// Get the shares of the managing key cluster given the hex representation of the key.
+///
+/// # Arguments
+///
+/// * `managing_cluster_key` - The managing cluster key to compute the shares from.
+///
+/// # Returns
+/// * A vector of JavaScript values. The first element is the x coordinate of the key, and the second element is the y coordinate of the key, in decimal.
+#[wasm_bindgen]
+pub fn get_managing_cluster_shares(managing_cluster_key: &str) -> Result<Vec<JsValue>, JsError> {
+    let key = jubjub_from_hex_string(managing_cluster_key)?;
+    Ok(vec![scalar_to_js(&key.x), scalar_to_js(&key.y)])
+}
+
+/// Parse an optional network argument, defaulting to testnet, which all keys
+/// were derived for before networks were distinguished
+fn parse_network(network: Option<String>) -> Result<Network, UtilsError> {
+    network.map_or(Ok(Network::default()), |network| network.parse())
+}
@@ -0,0 +1,54 @@
+//! Bindings for storing `sk_root` in a password-encrypted keystore
+
+use super::key_handles::{insert_key, require_key_from_js};
+use crate::{
+    errors::UtilsError,
+    key_handles::KeyHandle,
+    keystore::{decrypt_sk_root, encrypt_sk_root, KdfParams, Keystore},
+};
+use wasm_bindgen::prelude::*;
+
+/// Encrypts sk_root into a keystore under a password.
+///
+/// # Arguments
+///
+/// * `sk_root` - sk_root in hex, or the handle of a key held by the module.
+/// * `password` - The password to encrypt the keystore under.
+/// * `kdf` - The key derivation function, `scrypt` or `pbkdf2`, with the
+///   parameters of geth's standard keystores.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON representation of the keystore.
+#[wasm_bindgen]
+pub fn export_keystore(sk_root: &JsValue, password: &str, kdf: &str) -> Result<JsValue, JsError> {
+    let sk_root = require_key_from_js(sk_root)?;
+    let kdf = match kdf {
+        "scrypt" => KdfParams::standard_scrypt()?,
+        "pbkdf2" => KdfParams::standard_pbkdf2()?,
+        _ => return Err(UtilsError::Keystore(format!("unsupported kdf: {kdf}")).into()),
+    };
+
+    let keystore = encrypt_sk_root(&sk_root, password, kdf)?;
+    let serialized = serde_json::to_string(&keystore)
+        .map_err(|e| UtilsError::Serde(format!("error serializing keystore: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
+
+/// Decrypts sk_root from a keystore and holds it in the module.
+///
+/// # Arguments
+///
+/// * `keystore_str` - Serialized keystore, in the v3 keystore format.
+/// * `password` - The password the keystore is encrypted under.
+///
+/// # Returns
+///
+/// The handle referencing the decrypted sk_root.
+#[wasm_bindgen]
+pub fn import_keystore(keystore_str: &str, password: &str) -> Result<KeyHandle, JsError> {
+    let keystore: Keystore = serde_json::from_str(keystore_str)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing keystore: {e}")))?;
+    let sk_root = decrypt_sk_root(&keystore, password)?;
+    Ok(insert_key(sk_root)?)
+}
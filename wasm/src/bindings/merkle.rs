@@ -0,0 +1,77 @@
+//! Bindings for opening wallet commitments in the darkpool's Merkle tree
+
+use super::{scalar_to_js, scalars_from_js};
+use crate::{
+    errors::UtilsError,
+    helpers::scalar_from_hex_string,
+    merkle::{MerkleOpening, MerkleTree, MERKLE_HEIGHT},
+};
+use wasm_bindgen::prelude::*;
+
+/// Builds the opening of a leaf in a tree of the darkpool's height.
+///
+/// # Arguments
+///
+/// * `leaves` - The leaves of the tree in insertion order, as hex strings.
+/// * `leaf_index` - The index of the leaf to open.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON representation of the opening.
+#[wasm_bindgen]
+pub fn get_merkle_opening(leaves: Vec<JsValue>, leaf_index: u64) -> Result<JsValue, JsError> {
+    let leaves = scalars_from_js(&leaves)?;
+    let mut tree = MerkleTree::new(MERKLE_HEIGHT)?;
+    for leaf in leaves {
+        tree.insert(leaf)?;
+    }
+
+    let opening = tree.get_opening(leaf_index)?;
+    let serialized = serde_json::to_string(&opening)
+        .map_err(|e| UtilsError::Serde(format!("error serializing opening: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
+
+/// Computes the root that a Merkle opening opens its leaf under.
+///
+/// # Arguments
+///
+/// * `opening_str` - Serialized opening, with `value`, `leaf_index` and
+///   `path_siblings` fields.
+///
+/// # Returns
+///
+/// A `JsValue` containing the root.
+#[wasm_bindgen]
+pub fn compute_merkle_root(opening_str: &str) -> Result<JsValue, JsError> {
+    let opening = deserialize_opening(opening_str)?;
+    Ok(scalar_to_js(&opening.compute_root()?))
+}
+
+/// Verifies a Merkle opening against a root.
+///
+/// # Arguments
+///
+/// * `opening_str` - Serialized opening, with `value`, `leaf_index` and
+///   `path_siblings` fields.
+/// * `root` - The root of the tree, as a hex string.
+///
+/// # Returns
+///
+/// Whether the opening proves the inclusion of its leaf under the root.
+#[wasm_bindgen]
+pub fn verify_merkle_opening(opening_str: &str, root: &str) -> Result<bool, JsError> {
+    let opening = deserialize_opening(opening_str)?;
+    let root = scalar_from_hex_string(root)?;
+    match opening.verify(root) {
+        Ok(()) => Ok(true),
+        Err(UtilsError::InvalidMerkleProof(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Deserializes a JSON string into a `MerkleOpening`
+fn deserialize_opening(opening_str: &str) -> Result<MerkleOpening, UtilsError> {
+    serde_json::from_str(opening_str)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing opening: {e}")))
+}
@@ -0,0 +1,39 @@
+//! The `wasm-bindgen` exports of the crate, enabled by the `wasm` feature
+//!
+//! These are thin wrappers that parse the string arguments passed in from JS,
+//! call into the typed helpers of the crate, and encode the results back into
+//! `JsValue`s
+
+use crate::{errors::UtilsError, helpers::scalar_from_hex_string, types::ScalarField};
+use num_bigint::BigUint;
+use wasm_bindgen::JsValue;
+
+pub mod elgamal;
+pub mod field;
+pub mod key_handles;
+pub mod keys;
+pub mod keystore;
+pub mod merkle;
+pub mod permit2;
+pub mod signature;
+mod signer;
+pub mod wallet;
+
+/// Convert a scalar into a `JsValue` holding its decimal string representation
+pub(crate) fn scalar_to_js(scalar: &ScalarField) -> JsValue {
+    let bigint: BigUint = (*scalar).into();
+    JsValue::from_str(&bigint.to_string())
+}
+
+/// Parse a vector of `JsValue`s holding hex strings into scalars
+pub(crate) fn scalars_from_js(values: &[JsValue]) -> Result<Vec<ScalarField>, UtilsError> {
+    values
+        .iter()
+        .map(|value| {
+            let hex = value
+                .as_string()
+                .ok_or_else(|| UtilsError::InvalidHex("expected a hex string".to_string()))?;
+            scalar_from_hex_string(&hex)
+        })
+        .collect()
+}
@@ -0,0 +1,179 @@
+//! Bindings for the Permit2 permits authorizing deposits
+
+use super::{
+    signature::{encode_signature, to_verification_result},
+    signer::JsSigner,
+};
+use crate::{
+    errors::{ConversionError, UtilsError},
+    helpers::{
+        address_from_hex_string, biguint_from_hex_string, bytes_from_hex_string,
+        deserialize_external_transfer, pk_root_from_hex_string, to_contract_external_transfer,
+    },
+    permit2::{DepositPermit, Permit2Domain},
+    signature_encoding::SignatureEncoding,
+};
+use alloy_primitives::U256;
+use wasm_bindgen::prelude::*;
+
+/// Builds the EIP-712 typed data of a deposit's permit, to be passed to a
+/// wallet's `eth_signTypedData_v4`.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data of the deposit.
+/// * `pk_root` - The pk_root of the wallet deposited into, as hex-encoded SEC1
+///   bytes.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+/// * `permit2_address` - The hex-encoded address of the Permit2 contract.
+/// * `nonce` - The hex-encoded Permit2 nonce of the permit.
+/// * `deadline` - The hex-encoded timestamp the permit expires at, in seconds.
+///
+/// # Returns
+///
+/// The typed data as a JSON string.
+#[wasm_bindgen]
+pub fn get_deposit_permit_typed_data(
+    external_transfer_str: &str,
+    pk_root: &str,
+    chain_id: u64,
+    darkpool_address: &str,
+    permit2_address: &str,
+    nonce: &str,
+    deadline: &str,
+) -> Result<String, JsError> {
+    let permit = build_permit(
+        external_transfer_str,
+        pk_root,
+        darkpool_address,
+        nonce,
+        deadline,
+    )?;
+    let domain = Permit2Domain::new(chain_id, address_from_hex_string(permit2_address)?);
+    Ok(permit.typed_data(&domain).to_string())
+}
+
+/// Signs the permit authorizing a deposit, with the key of the account
+/// depositing.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data of the deposit.
+/// * `pk_root` - The pk_root of the wallet deposited into, as hex-encoded SEC1
+///   bytes.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+/// * `permit2_address` - The hex-encoded address of the Permit2 contract.
+/// * `nonce` - The hex-encoded Permit2 nonce of the permit.
+/// * `deadline` - The hex-encoded timestamp the permit expires at, in seconds.
+/// * `signer` - The depositing account's key in hex, a key handle, or a
+///   signing function.
+/// * `encoding` - The encoding of the signature, `rsv`, `rsv-legacy`, `compact`
+///   or `der`, defaulting to the `rsv-legacy` that Permit2 accepts.
+///
+/// # Returns
+///
+/// A `JsValue` containing the hex-encoded signature string, to be passed to
+/// the deposit as its `permitSignature`.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub async fn sign_deposit_permit(
+    external_transfer_str: String,
+    pk_root: String,
+    chain_id: u64,
+    darkpool_address: String,
+    permit2_address: String,
+    nonce: String,
+    deadline: String,
+    signer: JsValue,
+    encoding: Option<String>,
+) -> Result<JsValue, JsError> {
+    let permit = build_permit(
+        &external_transfer_str,
+        &pk_root,
+        &darkpool_address,
+        &nonce,
+        &deadline,
+    )?;
+    let domain = Permit2Domain::new(chain_id, address_from_hex_string(&permit2_address)?);
+    let signer = JsSigner::try_from(&signer)?;
+    let typed_data = permit.typed_data(&domain);
+    let sig = signer
+        .sign(&permit.signing_hash(&domain), Some(&typed_data))
+        .await?;
+    Ok(encode_signature(
+        &sig,
+        encoding,
+        SignatureEncoding::RsvLegacy,
+    )?)
+}
+
+/// Verifies the signature of a permit authorizing a deposit.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data of the deposit.
+/// * `pk_root` - The pk_root of the wallet deposited into, as hex-encoded SEC1
+///   bytes.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+/// * `permit2_address` - The hex-encoded address of the Permit2 contract.
+/// * `nonce` - The hex-encoded Permit2 nonce of the permit.
+/// * `deadline` - The hex-encoded timestamp the permit expires at, in seconds.
+/// * `signature` - The hex-encoded `rsv-legacy` or `compact` signature.
+///
+/// # Returns
+///
+/// Whether the signature is valid for the permit, i.e. whether it was signed
+/// by the depositing account, as Permit2 checks it.
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn verify_deposit_permit(
+    external_transfer_str: &str,
+    pk_root: &str,
+    chain_id: u64,
+    darkpool_address: &str,
+    permit2_address: &str,
+    nonce: &str,
+    deadline: &str,
+    signature: &str,
+) -> Result<bool, JsError> {
+    let permit = build_permit(
+        external_transfer_str,
+        pk_root,
+        darkpool_address,
+        nonce,
+        deadline,
+    )?;
+    let domain = Permit2Domain::new(chain_id, address_from_hex_string(permit2_address)?);
+    let sig_bytes = bytes_from_hex_string(signature)?;
+    to_verification_result(crate::signature::verify_deposit_permit_signature(
+        &permit, &domain, &sig_bytes,
+    ))
+}
+
+/// Build the permit of a deposit from the arguments of an export
+fn build_permit(
+    external_transfer_str: &str,
+    pk_root: &str,
+    darkpool_address: &str,
+    nonce: &str,
+    deadline: &str,
+) -> Result<DepositPermit, UtilsError> {
+    let external_transfer = deserialize_external_transfer(external_transfer_str)?;
+    let deposit = to_contract_external_transfer(&external_transfer)?;
+    DepositPermit::new(
+        deposit,
+        pk_root_from_hex_string(pk_root)?,
+        address_from_hex_string(darkpool_address)?,
+        u256_from_hex_string(nonce)?,
+        u256_from_hex_string(deadline)?,
+    )
+}
+
+/// Parse a `uint256` from a hex string
+fn u256_from_hex_string(hex: &str) -> Result<U256, UtilsError> {
+    let value = biguint_from_hex_string(hex)?;
+    U256::try_from(value).map_err(|_| ConversionError::InvalidUint.into())
+}
@@ -0,0 +1,513 @@
+//! Bindings for the signatures generated with `sk_root`
+//!
+//! The signing exports accept, in place of `sk_root` in hex, the handle of a
+//! key held by the module, see `import_key`, or a function that receives the
+//! hex-encoded prehash of the signed payload, and the EIP-712 typed data of
+//! the payload as a JSON string where it has any, and returns the hex-encoded
+//! `r || s || v` or compact signature over the prehash. The function may
+//! return a Promise of the signature, so every signing export returns a
+//! Promise, which resolves immediately for a key or handle
+
+use super::signer::JsSigner;
+use crate::{
+    eip712::{external_transfer_signing_hash, external_transfer_typed_data, Eip712Domain},
+    errors::UtilsError,
+    helpers::{
+        address_from_hex_string, address_to_checksum_hex_string, bytes_from_hex_string,
+        deserialize_external_transfer, deserialize_http_request, deserialize_wallet,
+        pk_root_from_hex_string, to_contract_external_transfer,
+    },
+    keys::address_from_pk_root,
+    signature::{
+        canonicalize_http_request, external_transfer_prehash, http_request_payload,
+        http_request_sig_header, message_prehash, wallet_update_prehash, SIG_VALIDITY_WINDOW_MS,
+    },
+    signature_encoding::{signature_from_bytes, RecoverableSignature, SignatureEncoding},
+};
+use ethers::types::Signature as EthersSignature;
+use wasm_bindgen::prelude::*;
+
+/// Generates wallet update statement signature.
+///
+/// # Arguments
+///
+/// * `wallet_str` - Serialized wallet data.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+/// * `encoding` - The encoding of the signature, `rsv`, `rsv-legacy`, `compact`
+///   or `der`, defaulting to `rsv`.
+///
+/// # Returns
+///
+/// A `JsValue` containing the hex-encoded signature string.
+#[wasm_bindgen]
+pub async fn generate_wallet_update_signature(
+    wallet_str: String,
+    signer: JsValue,
+    encoding: Option<String>,
+) -> Result<JsValue, JsError> {
+    let wallet = deserialize_wallet(&wallet_str)?;
+    let signer = JsSigner::try_from(&signer)?;
+    let sig = signer.sign(&wallet_update_prehash(&wallet), None).await?;
+    Ok(encode_signature(&sig, encoding, SignatureEncoding::Rsv)?)
+}
+
+/// Verifies a wallet update statement signature.
+///
+/// # Arguments
+///
+/// * `wallet_str` - Serialized wallet data.
+/// * `signature` - The hex-encoded `rsv` or `compact` signature.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// Whether the signature is valid for the wallet under `pk_root`.
+#[wasm_bindgen]
+pub fn verify_wallet_update_signature(
+    wallet_str: &str,
+    signature: &str,
+    pk_root: &str,
+) -> Result<bool, JsError> {
+    let wallet = deserialize_wallet(wallet_str)?;
+    let sig = ethers_signature_from_hex_string(signature)?;
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_wallet_update_signature(
+        &wallet,
+        &sig,
+        &verifying_key,
+    ))
+}
+
+/// Generates external transfer signature for withdrawals.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+/// * `encoding` - The encoding of the signature, `rsv`, `rsv-legacy`, `compact`
+///   or `der`, defaulting to `rsv`.
+///
+/// # Returns
+///
+/// A `JsValue` containing the hex-encoded signature string.
+#[wasm_bindgen]
+pub async fn generate_external_transfer_signature(
+    external_transfer_str: String,
+    signer: JsValue,
+    encoding: Option<String>,
+) -> Result<JsValue, JsError> {
+    let external_transfer = deserialize_external_transfer(&external_transfer_str)?;
+    let signer = JsSigner::try_from(&signer)?;
+    let contract_external_transfer = to_contract_external_transfer(&external_transfer)?;
+    let prehash = external_transfer_prehash(&contract_external_transfer)?;
+    let sig = signer.sign(&prehash, None).await?;
+    Ok(encode_signature(&sig, encoding, SignatureEncoding::Rsv)?)
+}
+
+/// Verifies an external transfer signature.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data.
+/// * `signature` - The hex-encoded `rsv` or `compact` signature.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// Whether the signature is valid for the transfer under `pk_root`.
+#[wasm_bindgen]
+pub fn verify_external_transfer_signature(
+    external_transfer_str: &str,
+    signature: &str,
+    pk_root: &str,
+) -> Result<bool, JsError> {
+    let external_transfer = deserialize_external_transfer(external_transfer_str)?;
+    let contract_external_transfer = to_contract_external_transfer(&external_transfer)?;
+    let sig = ethers_signature_from_hex_string(signature)?;
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_external_transfer_signature(
+        &contract_external_transfer,
+        &sig,
+        &verifying_key,
+    ))
+}
+
+/// Builds the EIP-712 typed data of an external transfer, to be passed to a
+/// wallet's `eth_signTypedData_v4`.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+///
+/// # Returns
+///
+/// The typed data as a JSON string.
+#[wasm_bindgen]
+pub fn get_external_transfer_typed_data(
+    external_transfer_str: &str,
+    chain_id: u64,
+    darkpool_address: &str,
+) -> Result<String, JsError> {
+    let external_transfer = deserialize_external_transfer(external_transfer_str)?;
+    let contract_external_transfer = to_contract_external_transfer(&external_transfer)?;
+    let domain = Eip712Domain::new(chain_id, address_from_hex_string(darkpool_address)?);
+    Ok(external_transfer_typed_data(&contract_external_transfer, &domain).to_string())
+}
+
+/// Generates an EIP-712 signature over an external transfer for withdrawals.
+///
+/// No deployed contract verifies this signature yet, the darkpool still
+/// requires the signature of `generate_external_transfer_signature`.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+/// * `encoding` - The encoding of the signature, `rsv`, `rsv-legacy`, `compact`
+///   or `der`, defaulting to `rsv`.
+///
+/// # Returns
+///
+/// A `JsValue` containing the hex-encoded signature string.
+#[wasm_bindgen]
+pub async fn generate_external_transfer_typed_signature(
+    external_transfer_str: String,
+    chain_id: u64,
+    darkpool_address: String,
+    signer: JsValue,
+    encoding: Option<String>,
+) -> Result<JsValue, JsError> {
+    let external_transfer = deserialize_external_transfer(&external_transfer_str)?;
+    let contract_external_transfer = to_contract_external_transfer(&external_transfer)?;
+    let domain = Eip712Domain::new(chain_id, address_from_hex_string(&darkpool_address)?);
+    let signer = JsSigner::try_from(&signer)?;
+    let typed_data = external_transfer_typed_data(&contract_external_transfer, &domain);
+    let prehash = external_transfer_signing_hash(&contract_external_transfer, &domain);
+    let sig = signer.sign(&prehash, Some(&typed_data)).await?;
+    Ok(encode_signature(&sig, encoding, SignatureEncoding::Rsv)?)
+}
+
+/// Verifies an EIP-712 signature over an external transfer.
+///
+/// # Arguments
+///
+/// * `external_transfer_str` - Serialized external transfer data.
+/// * `chain_id` - The ID of the chain the darkpool is deployed on.
+/// * `darkpool_address` - The hex-encoded address of the darkpool contract.
+/// * `signature` - The hex-encoded `rsv` or `compact` signature.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// Whether the signature is valid for the transfer under `pk_root`.
+#[wasm_bindgen]
+pub fn verify_external_transfer_typed_signature(
+    external_transfer_str: &str,
+    chain_id: u64,
+    darkpool_address: &str,
+    signature: &str,
+    pk_root: &str,
+) -> Result<bool, JsError> {
+    let external_transfer = deserialize_external_transfer(external_transfer_str)?;
+    let contract_external_transfer = to_contract_external_transfer(&external_transfer)?;
+    let domain = Eip712Domain::new(chain_id, address_from_hex_string(darkpool_address)?);
+    let sig = ethers_signature_from_hex_string(signature)?;
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_external_transfer_typed_signature(
+        &contract_external_transfer,
+        &domain,
+        &sig,
+        &verifying_key,
+    ))
+}
+
+/// Generates authorization headers for HTTP requests to a relayer.
+///
+/// # Arguments
+///
+/// * `message` - The message to be signed.
+/// * `timestamp` - The current timestamp.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+///
+/// # Returns
+///
+/// * A vector of JavaScript values. The first element is the signature header,
+///   and the second element is the expiration time of the signature.
+#[wasm_bindgen]
+pub async fn sign_http_request(
+    message: String,
+    timestamp: u64,
+    signer: JsValue,
+) -> Result<Vec<JsValue>, JsError> {
+    sign_http_request_payload(
+        message.as_bytes(),
+        timestamp,
+        SIG_VALIDITY_WINDOW_MS,
+        &signer,
+    )
+    .await
+}
+
+/// Generates authorization headers for HTTP requests to a relayer, valid for
+/// a caller-chosen window.
+///
+/// # Arguments
+///
+/// * `message` - The message to be signed.
+/// * `timestamp` - The current timestamp.
+/// * `validity_window_ms` - The number of milliseconds the signature is valid for.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+///
+/// # Returns
+///
+/// * A vector of JavaScript values. The first element is the signature header,
+///   and the second element is the expiration time of the signature.
+#[wasm_bindgen]
+pub async fn sign_http_request_with_window(
+    message: String,
+    timestamp: u64,
+    validity_window_ms: u64,
+    signer: JsValue,
+) -> Result<Vec<JsValue>, JsError> {
+    sign_http_request_payload(message.as_bytes(), timestamp, validity_window_ms, &signer).await
+}
+
+/// Generates authorization headers for an HTTP request to a relayer, signing
+/// the canonical form of its method, path, query, selected headers and body.
+///
+/// # Arguments
+///
+/// * `request_str` - Serialized request, with `method`, `path`, `query`,
+///   `headers` (an object of the headers to sign) and `body` fields.
+/// * `timestamp` - The current timestamp.
+/// * `validity_window_ms` - The number of milliseconds the signature is valid for.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+///
+/// # Returns
+///
+/// * A vector of JavaScript values. The first element is the value of the
+///   `renegade-auth` header, and the second element is the value of the
+///   `renegade-auth-expiration` header.
+#[wasm_bindgen]
+pub async fn sign_canonical_http_request(
+    request_str: String,
+    timestamp: u64,
+    validity_window_ms: u64,
+    signer: JsValue,
+) -> Result<Vec<JsValue>, JsError> {
+    let request = deserialize_http_request(&request_str)?;
+    let canonical_request = canonicalize_http_request(&request)?;
+    sign_http_request_payload(&canonical_request, timestamp, validity_window_ms, &signer).await
+}
+
+/// Verifies the authorization headers of an HTTP request signed in its
+/// canonical form.
+///
+/// # Arguments
+///
+/// * `request_str` - Serialized request, as passed to `sign_canonical_http_request`.
+/// * `expiration` - The expiration time of the signature.
+/// * `sig_header` - The signature header.
+/// * `current_timestamp` - The current timestamp.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// Whether the signature is valid under `pk_root` and has not expired.
+#[wasm_bindgen]
+pub fn verify_canonical_http_request(
+    request_str: &str,
+    expiration: u64,
+    sig_header: &str,
+    current_timestamp: u64,
+    pk_root: &str,
+) -> Result<bool, JsError> {
+    let request = deserialize_http_request(request_str)?;
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_canonical_http_request_signature(
+        &request,
+        expiration,
+        sig_header,
+        current_timestamp,
+        &verifying_key,
+    ))
+}
+
+/// Verifies the authorization headers of an HTTP request to a relayer.
+///
+/// # Arguments
+///
+/// * `message` - The message that was signed.
+/// * `expiration` - The expiration time of the signature.
+/// * `sig_header` - The signature header.
+/// * `current_timestamp` - The current timestamp.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// Whether the signature is valid under `pk_root` and has not expired.
+#[wasm_bindgen]
+pub fn verify_http_request_signature(
+    message: &str,
+    expiration: u64,
+    sig_header: &str,
+    current_timestamp: u64,
+    pk_root: &str,
+) -> Result<bool, JsError> {
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_http_request_signature(
+        message.as_bytes(),
+        expiration,
+        sig_header,
+        current_timestamp,
+        &verifying_key,
+    ))
+}
+
+/// Sign a message with sk_root
+///
+/// # Arguments
+///
+/// * `message` - The message to be signed.
+/// * `signer` - sk_root in hex, a key handle, a signing function, or
+///   `{ sign, pkRoot }` to check the function's signatures against pkRoot.
+/// * `encoding` - The encoding of the signature, `rsv`, `rsv-legacy`, `compact`
+///   or `der`, defaulting to the 64-byte `r || s` accepted by `verify_message`.
+///
+/// # Returns
+///
+/// * A `JsValue` containing the hexadecimal string representation of the signature.
+#[wasm_bindgen]
+pub async fn sign_message(
+    message: String,
+    signer: JsValue,
+    encoding: Option<String>,
+) -> Result<JsValue, JsError> {
+    let signer = JsSigner::try_from(&signer)?;
+    let sig = signer
+        .sign(&message_prehash(message.as_bytes()), None)
+        .await?;
+    let sig_bytes = match encoding {
+        Some(encoding) => sig.encode(encoding.parse()?),
+        None => sig.signature().to_bytes().to_vec(),
+    };
+    Ok(JsValue::from_str(&hex::encode(sig_bytes)))
+}
+
+/// Verify a message signed with sk_root
+///
+/// # Arguments
+///
+/// * `message` - The message that was signed.
+/// * `signature` - The hex-encoded signature, in any of the encodings of
+///   `sign_message`.
+/// * `pk_root` - pk_root as hex-encoded SEC1 bytes.
+///
+/// # Returns
+///
+/// * Whether the signature is valid for the message under `pk_root`.
+#[wasm_bindgen]
+pub fn verify_message(message: &str, signature: &str, pk_root: &str) -> Result<bool, JsError> {
+    let sig_bytes = bytes_from_hex_string(signature)?;
+    let sig = signature_from_bytes(&sig_bytes)?;
+    let verifying_key = pk_root_from_hex_string(pk_root)?;
+    to_verification_result(crate::signature::verify_message(
+        message.as_bytes(),
+        &sig,
+        &verifying_key,
+    ))
+}
+
+/// Computes the hash an Ethereum wallet signs when asked to `personal_sign` a
+/// message, as specified in EIP-191.
+///
+/// # Arguments
+///
+/// * `message` - The message to be signed.
+///
+/// # Returns
+///
+/// * A `JsValue` containing the hex-encoded hash.
+#[wasm_bindgen]
+pub fn personal_sign_hash(message: &str) -> JsValue {
+    let hash = crate::signature::personal_sign_hash(message.as_bytes());
+    JsValue::from_str(&format!("0x{}", hex::encode(hash)))
+}
+
+/// Recovers the Ethereum address that signed a message with `personal_sign`.
+///
+/// # Arguments
+///
+/// * `message` - The message that was signed.
+/// * `signature` - The hex-encoded 65-byte `r || s || v` or 64-byte compact signature.
+///
+/// # Returns
+///
+/// * A `JsValue` containing the checksummed address of the signer.
+#[wasm_bindgen]
+pub fn recover_signer(message: &str, signature: &str) -> Result<JsValue, JsError> {
+    // Wallets may return either form of `s`, which `ecrecover` accepts alike
+    let sig_bytes = bytes_from_hex_string(signature)?;
+    let sig = RecoverableSignature::from_bytes_malleable(&sig_bytes)?.to_ethers();
+    let prehash = crate::signature::personal_sign_hash(message.as_bytes());
+    let signer = crate::signature::recover_signer(&prehash, &sig)?;
+    let address = address_from_pk_root(&signer);
+    Ok(JsValue::from_str(&address_to_checksum_hex_string(&address)))
+}
+
+/// Sign the payload authorizing an HTTP request, see `sign_http_request_with_window`
+async fn sign_http_request_payload(
+    message: &[u8],
+    timestamp: u64,
+    validity_window_ms: u64,
+    signer: &JsValue,
+) -> Result<Vec<JsValue>, JsError> {
+    let signer = JsSigner::try_from(signer)?;
+    let (payload, expiration) = http_request_payload(message, timestamp, validity_window_ms);
+    let sig = signer.sign(&message_prehash(&payload), None).await?;
+    Ok(vec![
+        JsValue::from_str(&http_request_sig_header(&sig)),
+        JsValue::from_str(&expiration.to_string()),
+    ])
+}
+
+/// Parse a 65-byte `r || s || v` or 64-byte EIP-2098 compact signature from a
+/// hex string
+pub(crate) fn ethers_signature_from_hex_string(hex: &str) -> Result<EthersSignature, UtilsError> {
+    let sig_bytes = bytes_from_hex_string(hex)?;
+    RecoverableSignature::from_bytes(&sig_bytes).map(|sig| sig.to_ethers())
+}
+
+/// Encode a signature as a hex string in the given encoding, or the default
+/// if none is given
+pub(crate) fn encode_signature(
+    signature: &RecoverableSignature,
+    encoding: Option<String>,
+    default_encoding: SignatureEncoding,
+) -> Result<JsValue, UtilsError> {
+    let encoding = match encoding {
+        Some(encoding) => encoding.parse()?,
+        None => default_encoding,
+    };
+    Ok(JsValue::from_str(&hex::encode(signature.encode(encoding))))
+}
+
+/// Map the result of a verification onto a boolean, so that a bad or expired
+/// signature is reported as `false` rather than thrown
+pub(crate) fn to_verification_result(res: Result<(), UtilsError>) -> Result<bool, JsError> {
+    match res {
+        Ok(()) => Ok(true),
+        Err(UtilsError::InvalidSignature(_) | UtilsError::SignatureExpired(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,146 @@
+//! The signers accepted by the signing exports in place of `sk_root`
+
+use super::key_handles::key_from_js;
+use crate::{
+    errors::UtilsError,
+    helpers::{bytes_from_hex_string, pk_root_from_hex_string},
+    signature::check_signature,
+    signature_encoding::RecoverableSignature,
+    signer::Signer,
+};
+use ethers::{
+    core::k256::ecdsa::{SigningKey, VerifyingKey},
+    types::Signature as EthersSignature,
+};
+use js_sys::{Function, Promise, Reflect};
+use serde_json::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// A signer passed in from JS, either `sk_root` in hex, the handle of a key
+/// held by the module, or a function that signs the hex-encoded prehash and
+/// returns the hex-encoded `r || s || v` or compact signature
+///
+/// When the signed payload is EIP-712 typed data, the function also receives
+/// the typed data as a JSON string, e.g. to pass to `eth_signTypedData_v4`
+///
+/// The function may return the signature directly or a Promise of it. It may
+/// also be passed as `{ sign, pkRoot }`, with `pkRoot` the hex-encoded SEC1
+/// key its signatures are checked to recover to
+pub(crate) enum JsSigner {
+    /// A key held in memory
+    Key(SigningKey),
+    /// A JS signing function, e.g. wrapping a secure enclave or a remote
+    /// signing service
+    Callback {
+        /// The signing function
+        sign_fn: Function,
+        /// The key the function's signatures must recover to, if given
+        pk_root: Option<VerifyingKey>,
+    },
+}
+
+impl TryFrom<&JsValue> for JsSigner {
+    type Error = UtilsError;
+
+    fn try_from(value: &JsValue) -> Result<Self, Self::Error> {
+        if let Some(signing_key) = key_from_js(value)? {
+            return Ok(JsSigner::Key(signing_key));
+        }
+
+        if let Some(sign_fn) = value.dyn_ref::<Function>() {
+            return Ok(JsSigner::Callback {
+                sign_fn: sign_fn.clone(),
+                pk_root: None,
+            });
+        }
+
+        if value.is_object() {
+            let sign_fn = Reflect::get(value, &JsValue::from_str("sign"))
+                .ok()
+                .and_then(|f| f.dyn_into::<Function>().ok())
+                .ok_or_else(|| {
+                    UtilsError::Signing("signer object must have a sign function".to_string())
+                })?;
+            let pk_root = Reflect::get(value, &JsValue::from_str("pkRoot"))
+                .ok()
+                .and_then(|pk_root| pk_root.as_string())
+                .map(|pk_root| pk_root_from_hex_string(&pk_root))
+                .transpose()?;
+            return Ok(JsSigner::Callback { sign_fn, pk_root });
+        }
+
+        Err(UtilsError::Signing(
+            "expected sk_root in hex, a key handle or a signing function".to_string(),
+        ))
+    }
+}
+
+impl JsSigner {
+    /// Sign the given prehash, and its typed data if any, awaiting the signing
+    /// function if it returns a Promise
+    ///
+    /// The signature is normalized, and checked against the function's
+    /// `pkRoot` if given, with [`check_signature`]
+    pub(crate) async fn sign(
+        &self,
+        prehash: &[u8; 32],
+        typed_data: Option<&Value>,
+    ) -> Result<RecoverableSignature, UtilsError> {
+        match self {
+            JsSigner::Key(signing_key) => {
+                let sig = signing_key.sign_prehash(prehash)?;
+                check_signature(prehash, &sig, None /* pk_root */)
+            }
+            JsSigner::Callback { sign_fn, pk_root } => {
+                let sig = call_signing_function(sign_fn, prehash, typed_data).await?;
+                check_signature(prehash, &sig, pk_root.as_ref())
+            }
+        }
+    }
+}
+
+/// Call a JS signing function on the given prehash and typed data, awaiting
+/// its signature if it returns a Promise
+async fn call_signing_function(
+    f: &Function,
+    prehash: &[u8; 32],
+    typed_data: Option<&Value>,
+) -> Result<EthersSignature, UtilsError> {
+    let (prehash, typed_data) = signing_function_args(prehash, typed_data);
+    let mut res = f
+        .call2(&JsValue::NULL, &prehash, &typed_data)
+        .map_err(|e| UtilsError::Signing(format!("signing function threw: {e:?}")))?;
+    if let Some(promise) = res.dyn_ref::<Promise>() {
+        res = JsFuture::from(promise.clone())
+            .await
+            .map_err(|e| UtilsError::Signing(format!("signing function rejected: {e:?}")))?;
+    }
+
+    signature_from_js(&res)
+}
+
+/// Encode the arguments of a JS signing function: the hex-encoded prehash,
+/// and the typed data as a JSON string or `undefined` if there is none
+fn signing_function_args(prehash: &[u8; 32], typed_data: Option<&Value>) -> (JsValue, JsValue) {
+    let prehash = JsValue::from_str(&format!("0x{}", hex::encode(prehash)));
+    let typed_data = typed_data
+        .map(|typed_data| JsValue::from_str(&typed_data.to_string()))
+        .unwrap_or(JsValue::UNDEFINED);
+    (prehash, typed_data)
+}
+
+/// Parse the hex-encoded signature returned by a JS signing function
+///
+/// The signature may be the 65 byte `r || s || v` or the 64 byte compact
+/// encoding, in either form of `s`, as enclaves do not all normalize it
+fn signature_from_js(value: &JsValue) -> Result<EthersSignature, UtilsError> {
+    let sig_hex = value.as_string().ok_or_else(|| {
+        UtilsError::Signing("signing function must return a hex string".to_string())
+    })?;
+
+    let sig_bytes = bytes_from_hex_string(&sig_hex)?;
+    RecoverableSignature::from_bytes_malleable(&sig_bytes)
+        .map(|sig| sig.to_ethers())
+        .map_err(|e| UtilsError::Signing(format!("signing function returned {e}")))
+}
@@ -0,0 +1,165 @@
+//! Bindings for packing and unpacking wallets
+
+use super::{scalar_to_js, scalars_from_js};
+use crate::{
+    errors::UtilsError,
+    helpers::{deserialize_wallet, scalar_from_hex_string},
+    types::{ApiWallet, WalletShares},
+    wallet::{
+        shares::{self, derive_wallet_shares, evaluate_hash_chain as _evaluate_hash_chain},
+        Wallet,
+    },
+};
+use wasm_bindgen::prelude::*;
+
+/// Reconstructs the plaintext wallet from its shares.
+///
+/// # Arguments
+///
+/// * `wallet_str` - Serialized wallet data, holding the blinded public shares
+///   and private shares.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON representation of the wallet's balances,
+/// orders, keychain, match fee, managing cluster and blinder.
+#[wasm_bindgen]
+pub fn unpack_wallet(wallet_str: &str) -> Result<JsValue, JsError> {
+    let shares = deserialize_wallet(wallet_str)?;
+    let wallet = Wallet::from_shares(&shares)?;
+    let serialized = serde_json::to_string(&wallet)
+        .map_err(|e| UtilsError::Serde(format!("error serializing wallet: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
+
+/// Computes a chained Poseidon hash of the given length from the given seed.
+///
+/// # Arguments
+///
+/// * `seed` - The seed of the chain, as a hex string.
+/// * `length` - The number of elements of the chain to compute.
+///
+/// # Returns
+///
+/// A vector of JavaScript values, the decimal string representations of the
+/// chain's elements.
+#[wasm_bindgen]
+pub fn evaluate_hash_chain(seed: &str, length: usize) -> Result<Vec<JsValue>, JsError> {
+    let seed = scalar_from_hex_string(seed)?;
+    let chain = _evaluate_hash_chain(seed, length);
+    Ok(chain.iter().map(scalar_to_js).collect())
+}
+
+/// Creates a secret sharing of a packed wallet given the secret shares and blinders.
+///
+/// # Arguments
+///
+/// * `packed_wallet` - The packed wallet, as hex strings.
+/// * `blinder` - The wallet blinder, as a hex string.
+/// * `private_blinder_share` - The private share of the blinder, as a hex string.
+/// * `secret_shares` - The private shares of the wallet, as hex strings.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON serialized blinded public shares and private shares.
+#[wasm_bindgen]
+pub fn create_wallet_shares_with_randomness(
+    packed_wallet: Vec<JsValue>,
+    blinder: &str,
+    private_blinder_share: &str,
+    secret_shares: Vec<JsValue>,
+) -> Result<JsValue, JsError> {
+    let shares = shares::create_wallet_shares_with_randomness(
+        &scalars_from_js(&packed_wallet)?,
+        scalar_from_hex_string(blinder)?,
+        scalar_from_hex_string(private_blinder_share)?,
+        &scalars_from_js(&secret_shares)?,
+    )?;
+    serialize_shares(&shares)
+}
+
+/// Derives the initial shares of a packed wallet from its seeds.
+///
+/// # Arguments
+///
+/// * `packed_wallet` - The packed wallet, as hex strings.
+/// * `blinder_seed` - The seed of the blinder stream, as a hex string.
+/// * `share_seed` - The seed of the secret share stream, as a hex string.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON serialized blinded public shares and private shares.
+#[wasm_bindgen]
+pub fn derive_shares(
+    packed_wallet: Vec<JsValue>,
+    blinder_seed: &str,
+    share_seed: &str,
+) -> Result<JsValue, JsError> {
+    let shares = derive_wallet_shares(
+        &scalars_from_js(&packed_wallet)?,
+        scalar_from_hex_string(blinder_seed)?,
+        scalar_from_hex_string(share_seed)?,
+    )?;
+    serialize_shares(&shares)
+}
+
+/// Reblinds a packed wallet, consuming the next blinder and secret shares.
+///
+/// # Arguments
+///
+/// * `packed_wallet` - The packed wallet, as hex strings.
+/// * `private_shares` - The current private shares of the wallet, as hex strings.
+///
+/// # Returns
+///
+/// A `JsValue` containing the JSON serialized blinded public shares and private shares.
+#[wasm_bindgen]
+pub fn reblind_wallet(
+    packed_wallet: Vec<JsValue>,
+    private_shares: Vec<JsValue>,
+) -> Result<JsValue, JsError> {
+    let shares = shares::reblind(
+        &scalars_from_js(&packed_wallet)?,
+        &scalars_from_js(&private_shares)?,
+    )?;
+    serialize_shares(&shares)
+}
+
+/// Computes the commitment to a wallet's shares, its leaf in the state tree.
+///
+/// # Arguments
+///
+/// * `wallet_str` - Serialized wallet data, holding the blinded public shares
+///   and private shares.
+///
+/// # Returns
+///
+/// A `JsValue` containing the commitment.
+#[wasm_bindgen]
+pub fn compute_wallet_commitment(wallet_str: &str) -> Result<JsValue, JsError> {
+    let shares = deserialize_wallet(wallet_str)?;
+    Ok(scalar_to_js(&shares.get_share_commitment()))
+}
+
+/// Computes the nullifier spent by the next update to a wallet.
+///
+/// # Arguments
+///
+/// * `wallet_str` - Serialized wallet data, holding the blinded public shares
+///   and private shares.
+///
+/// # Returns
+///
+/// A `JsValue` containing the nullifier.
+#[wasm_bindgen]
+pub fn compute_wallet_nullifier(wallet_str: &str) -> Result<JsValue, JsError> {
+    let shares = deserialize_wallet(wallet_str)?;
+    Ok(scalar_to_js(&shares.get_nullifier()?))
+}
+
+/// Serialize wallet shares in the format accepted by the other wallet bindings
+fn serialize_shares(shares: &WalletShares) -> Result<JsValue, JsError> {
+    let serialized = serde_json::to_string(&ApiWallet::from(shares))
+        .map_err(|e| UtilsError::Serde(format!("error serializing wallet shares: {e}")))?;
+    Ok(JsValue::from_str(&serialized))
+}
@@ -0,0 +1,228 @@
+//! EIP-712 typed data for the payloads signed with `sk_root`
+//!
+//! Signing the typed data hash rather than an opaque serialization lets
+//! hardware and browser wallets display the fields of the payload, e.g. the
+//! account, mint, amount and direction of an external transfer, before the
+//! user signs it
+//!
+//! Withdrawals are authorized by `sk_root`, as with the calldata signature of
+//! [`crate::signature::gen_external_transfer_signature`]. No deployed contract
+//! verifies signatures in the `Renegade` domain yet, so the darkpool still
+//! requires the calldata signature until it does
+
+use crate::types::ContractExternalTransfer;
+use alloy_primitives::{Address, U256};
+use ethers::utils::keccak256;
+use serde_json::{json, Value};
+
+/// The name of the EIP-712 signing domain
+pub const EIP712_DOMAIN_NAME: &str = "Renegade";
+/// The version of the EIP-712 signing domain
+pub const EIP712_DOMAIN_VERSION: &str = "1";
+
+/// The EIP-712 type of the signing domain
+const EIP712_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+/// The EIP-712 type of an external transfer
+const EXTERNAL_TRANSFER_TYPE: &str =
+    "ExternalTransfer(address account,address mint,uint256 amount,bool isWithdrawal)";
+/// The name of the external transfer type
+const EXTERNAL_TRANSFER_TYPE_NAME: &str = "ExternalTransfer";
+/// The prefix of an EIP-712 signing hash
+const EIP712_PREFIX: [u8; 2] = [0x19, 0x01];
+
+/// The EIP-712 signing domain, binding signatures to a chain and the darkpool
+/// contract deployed on it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Eip712Domain {
+    /// The ID of the chain the darkpool is deployed on
+    pub chain_id: u64,
+    /// The address of the darkpool contract
+    pub verifying_contract: Address,
+}
+
+impl Eip712Domain {
+    /// Create a domain for the darkpool at the given address and chain
+    pub fn new(chain_id: u64, verifying_contract: Address) -> Self {
+        Self {
+            chain_id,
+            verifying_contract,
+        }
+    }
+
+    /// The domain separator, the hash of the domain struct
+    pub fn separator(&self) -> [u8; 32] {
+        keccak256(
+            [
+                keccak256(EIP712_DOMAIN_TYPE),
+                keccak256(EIP712_DOMAIN_NAME),
+                keccak256(EIP712_DOMAIN_VERSION),
+                encode_uint(U256::from(self.chain_id)),
+                encode_address(&self.verifying_contract),
+            ]
+            .concat(),
+        )
+    }
+
+    /// The domain as the JSON `domain` of an `eth_signTypedData_v4` request
+    pub fn to_json(&self) -> Value {
+        json!({
+            "name": EIP712_DOMAIN_NAME,
+            "version": EIP712_DOMAIN_VERSION,
+            "chainId": self.chain_id,
+            "verifyingContract": address_to_hex_string(&self.verifying_contract),
+        })
+    }
+}
+
+/// Compute the EIP-712 struct hash of an external transfer
+pub fn external_transfer_struct_hash(transfer: &ContractExternalTransfer) -> [u8; 32] {
+    keccak256(
+        [
+            keccak256(EXTERNAL_TRANSFER_TYPE),
+            encode_address(&transfer.account_addr),
+            encode_address(&transfer.mint),
+            encode_uint(transfer.amount),
+            encode_uint(U256::from(transfer.is_withdrawal as u8)),
+        ]
+        .concat(),
+    )
+}
+
+/// Compute the EIP-712 signing hash of an external transfer in the given
+/// domain
+pub fn external_transfer_signing_hash(
+    transfer: &ContractExternalTransfer,
+    domain: &Eip712Domain,
+) -> [u8; 32] {
+    typed_data_signing_hash(
+        &domain.separator(),
+        &external_transfer_struct_hash(transfer),
+    )
+}
+
+/// Build the `eth_signTypedData_v4` request of an external transfer in the
+/// given domain, for a wallet to display and sign
+pub fn external_transfer_typed_data(
+    transfer: &ContractExternalTransfer,
+    domain: &Eip712Domain,
+) -> Value {
+    json!({
+        "types": {
+            "EIP712Domain": [
+                { "name": "name", "type": "string" },
+                { "name": "version", "type": "string" },
+                { "name": "chainId", "type": "uint256" },
+                { "name": "verifyingContract", "type": "address" },
+            ],
+            EXTERNAL_TRANSFER_TYPE_NAME: external_transfer_type_fields(),
+        },
+        "primaryType": EXTERNAL_TRANSFER_TYPE_NAME,
+        "domain": domain.to_json(),
+        "message": external_transfer_message(transfer),
+    })
+}
+
+/// The fields of the external transfer type, as listed in the `types` of an
+/// `eth_signTypedData_v4` request
+fn external_transfer_type_fields() -> Value {
+    json!([
+        { "name": "account", "type": "address" },
+        { "name": "mint", "type": "address" },
+        { "name": "amount", "type": "uint256" },
+        { "name": "isWithdrawal", "type": "bool" },
+    ])
+}
+
+/// An external transfer as the JSON message of an `eth_signTypedData_v4`
+/// request
+fn external_transfer_message(transfer: &ContractExternalTransfer) -> Value {
+    json!({
+        "account": address_to_hex_string(&transfer.account_addr),
+        "mint": address_to_hex_string(&transfer.mint),
+        "amount": transfer.amount.to_string(),
+        "isWithdrawal": transfer.is_withdrawal,
+    })
+}
+
+/// Compute the EIP-712 signing hash of a struct hash under the given domain
+/// separator
+pub(crate) fn typed_data_signing_hash(
+    domain_separator: &[u8; 32],
+    struct_hash: &[u8; 32],
+) -> [u8; 32] {
+    keccak256([EIP712_PREFIX.as_slice(), domain_separator, struct_hash].concat())
+}
+
+/// ABI encode an address as a 32 byte word
+pub(crate) fn encode_address(address: &Address) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_slice());
+    word
+}
+
+/// ABI encode an unsigned integer as a 32 byte word
+pub(crate) fn encode_uint(value: U256) -> [u8; 32] {
+    value.to_be_bytes::<32>()
+}
+
+/// Format an address as a lowercase hex string
+pub(crate) fn address_to_hex_string(address: &Address) -> String {
+    format!("0x{}", hex::encode(address.as_slice()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{
+        transaction::eip712::{EIP712Domain, Eip712, TypedData},
+        H160,
+    };
+
+    /// Build a domain for a mock darkpool
+    fn mock_domain() -> Eip712Domain {
+        Eip712Domain::new(421614 /* chain_id */, Address::from([0xda; 20]))
+    }
+
+    /// Build a mock withdrawal
+    fn mock_transfer() -> ContractExternalTransfer {
+        ContractExternalTransfer {
+            account_addr: Address::from([0x11; 20]),
+            mint: Address::from([0x22; 20]),
+            amount: U256::from(1_000_000u64),
+            is_withdrawal: true,
+        }
+    }
+
+    #[test]
+    fn test_domain_separator() {
+        let domain = mock_domain();
+        let ethers_domain = EIP712Domain {
+            name: Some(EIP712_DOMAIN_NAME.to_string()),
+            version: Some(EIP712_DOMAIN_VERSION.to_string()),
+            chain_id: Some(domain.chain_id.into()),
+            verifying_contract: Some(H160::from_slice(domain.verifying_contract.as_slice())),
+            salt: None,
+        };
+        assert_eq!(domain.separator(), ethers_domain.separator());
+    }
+
+    #[test]
+    fn test_typed_data_matches_signing_hash() {
+        // A wallet signing the typed data signs the same hash as the helpers
+        let (transfer, domain) = (mock_transfer(), mock_domain());
+        let typed_data: TypedData =
+            serde_json::from_value(external_transfer_typed_data(&transfer, &domain)).unwrap();
+        assert_eq!(
+            typed_data.encode_eip712().unwrap(),
+            external_transfer_signing_hash(&transfer, &domain)
+        );
+
+        // The hash is bound to the domain
+        let other_domain = Eip712Domain::new(1 /* chain_id */, domain.verifying_contract);
+        assert_ne!(
+            external_transfer_signing_hash(&transfer, &domain),
+            external_transfer_signing_hash(&transfer, &other_domain)
+        );
+    }
+}
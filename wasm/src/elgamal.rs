@@ -0,0 +1,211 @@
+//! ElGamal encryption of scalar vectors under a BabyJubJub key
+//!
+//! As in the relayer, the sender samples an ephemeral key and derives a shared
+//! secret with the recipient's [`EncryptionKey`]. The x coordinate of the
+//! shared secret seeds a Poseidon hash chain, which is used as a stream cipher
+//! over the message
+
+use crate::{
+    errors::UtilsError,
+    helpers::{deserialize_scalars_from_hex_strings, serialize_scalars_to_hex_strings},
+    types::{
+        BabyJubJubPoint, EmbeddedCurveConfig, EmbeddedScalarField, EncryptionKey, ScalarField,
+    },
+    wallet::shares::evaluate_hash_chain,
+};
+use ark_ec::{twisted_edwards::Projective, CurveGroup, Group};
+use ark_ff::PrimeField;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter, Result as FmtResult};
+use zeroize::Zeroize;
+
+/// A decryption key in the ElGamal over BabyJubJub cryptosystem
+///
+/// The key is zeroized when dropped, and redacted when formatted
+pub struct DecryptionKey {
+    key: EmbeddedScalarField,
+}
+
+impl Drop for DecryptionKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Debug for DecryptionKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("DecryptionKey(<redacted>)")
+    }
+}
+
+impl From<EmbeddedScalarField> for DecryptionKey {
+    fn from(key: EmbeddedScalarField) -> Self {
+        Self { key }
+    }
+}
+
+impl DecryptionKey {
+    /// The secret scalar
+    pub fn key(&self) -> &EmbeddedScalarField {
+        &self.key
+    }
+}
+
+/// The number of random bytes reduced into a scalar when sampling, wide enough
+/// that the reduction is statistically uniform
+const SCALAR_SAMPLE_BYTES: usize = 64;
+
+/// A ciphertext in the ElGamal over BabyJubJub cryptosystem
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ElGamalCiphertext {
+    /// The ephemeral key, from which the recipient derives the shared secret
+    pub ephemeral_key: BabyJubJubPoint,
+    /// The encrypted message
+    #[serde(
+        serialize_with = "serialize_scalars_to_hex_strings",
+        deserialize_with = "deserialize_scalars_from_hex_strings"
+    )]
+    pub ciphertext: Vec<ScalarField>,
+}
+
+/// Sample a uniformly random scalar of the embedded curve's scalar field
+pub fn random_embedded_scalar() -> Result<EmbeddedScalarField, UtilsError> {
+    let mut bytes = [0u8; SCALAR_SAMPLE_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| UtilsError::Randomness(e.to_string()))?;
+    Ok(EmbeddedScalarField::from_le_bytes_mod_order(&bytes))
+}
+
+/// Generate a random keypair, returning the decryption key followed by the
+/// encryption key
+pub fn generate_keypair() -> Result<(DecryptionKey, EncryptionKey), UtilsError> {
+    let decryption_key = DecryptionKey::from(random_embedded_scalar()?);
+    let encryption_key = get_encryption_key(&decryption_key);
+    Ok((decryption_key, encryption_key))
+}
+
+/// Get the encryption key corresponding to a decryption key
+pub fn get_encryption_key(decryption_key: &DecryptionKey) -> EncryptionKey {
+    (Projective::<EmbeddedCurveConfig>::generator() * decryption_key.key()).into()
+}
+
+/// Encrypt a message under the given key with freshly sampled randomness
+///
+/// Returns the ciphertext and the randomness used to generate it
+pub fn encrypt(
+    message: &[ScalarField],
+    encryption_key: &EncryptionKey,
+) -> Result<(ElGamalCiphertext, EmbeddedScalarField), UtilsError> {
+    let randomness = random_embedded_scalar()?;
+    let ciphertext = encrypt_with_randomness(message, randomness, encryption_key)?;
+    Ok((ciphertext, randomness))
+}
+
+/// Encrypt a message under the given key using the given randomness as the
+/// ephemeral secret key
+pub fn encrypt_with_randomness(
+    message: &[ScalarField],
+    randomness: EmbeddedScalarField,
+    encryption_key: &EncryptionKey,
+) -> Result<ElGamalCiphertext, UtilsError> {
+    let key = Projective::<EmbeddedCurveConfig>::try_from(*encryption_key)?;
+    let ephemeral_key = Projective::<EmbeddedCurveConfig>::generator() * randomness;
+    let keystream = get_keystream(key * randomness, message.len());
+
+    let ciphertext = message.iter().zip(keystream).map(|(m, k)| *m + k).collect();
+    Ok(ElGamalCiphertext {
+        ephemeral_key: ephemeral_key.into(),
+        ciphertext,
+    })
+}
+
+/// Decrypt a ciphertext with the given decryption key
+pub fn decrypt(
+    ciphertext: &ElGamalCiphertext,
+    decryption_key: &DecryptionKey,
+) -> Result<Vec<ScalarField>, UtilsError> {
+    let ephemeral_key = Projective::<EmbeddedCurveConfig>::try_from(ciphertext.ephemeral_key)?;
+    let keystream = get_keystream(
+        ephemeral_key * decryption_key.key(),
+        ciphertext.ciphertext.len(),
+    );
+
+    Ok(ciphertext
+        .ciphertext
+        .iter()
+        .zip(keystream)
+        .map(|(c, k)| *c - k)
+        .collect())
+}
+
+/// Compute the stream cipher keystream seeded by the given shared secret
+fn get_keystream(
+    shared_secret: Projective<EmbeddedCurveConfig>,
+    length: usize,
+) -> Vec<ScalarField> {
+    let seed = shared_secret.into_affine().x;
+    evaluate_hash_chain(seed, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a message of the given length
+    fn mock_message(length: u64) -> Vec<ScalarField> {
+        (0..length).map(|i| ScalarField::from(i * 7 + 1)).collect()
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let (decryption_key, encryption_key) = generate_keypair().unwrap();
+        let message = mock_message(10);
+        let (ciphertext, _) = encrypt(&message, &encryption_key).unwrap();
+        assert_ne!(ciphertext.ciphertext, message);
+        assert_eq!(decrypt(&ciphertext, &decryption_key).unwrap(), message);
+
+        // A different key does not recover the message
+        let (other_key, _) = generate_keypair().unwrap();
+        assert_ne!(decrypt(&ciphertext, &other_key).unwrap(), message);
+    }
+
+    #[test]
+    fn test_encrypt_with_randomness() {
+        let decryption_key = DecryptionKey::from(EmbeddedScalarField::from(3u8));
+        let encryption_key = get_encryption_key(&decryption_key);
+        let randomness = EmbeddedScalarField::from(5u8);
+        let message = mock_message(3);
+        let ciphertext = encrypt_with_randomness(&message, randomness, &encryption_key).unwrap();
+
+        // The shared secret is the generator scaled by both secrets
+        let generator = Projective::<EmbeddedCurveConfig>::generator();
+        let shared_secret = generator * EmbeddedScalarField::from(15u8);
+        let keystream = evaluate_hash_chain(shared_secret.into_affine().x, 3 /* length */);
+        assert_eq!(ciphertext.ephemeral_key, (generator * randomness).into());
+        assert_eq!(ciphertext.ciphertext[2], message[2] + keystream[2]);
+    }
+
+    #[test]
+    fn test_decryption_key_redacted() {
+        let decryption_key = DecryptionKey::from(EmbeddedScalarField::from(3u8));
+        assert_eq!(format!("{decryption_key:?}"), "DecryptionKey(<redacted>)");
+    }
+
+    #[test]
+    fn test_ciphertext_serde() {
+        let (_, encryption_key) = generate_keypair().unwrap();
+        let (ciphertext, _) = encrypt(&mock_message(4), &encryption_key).unwrap();
+        let serialized = serde_json::to_string(&ciphertext).unwrap();
+        let deserialized: ElGamalCiphertext = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, ciphertext);
+    }
+
+    #[test]
+    fn test_invalid_encryption_key() {
+        let key = BabyJubJubPoint {
+            x: ScalarField::from(1u8),
+            y: ScalarField::from(2u8),
+        };
+        let err = encrypt(&mock_message(1), &key).unwrap_err();
+        assert_eq!(err.code(), "INVALID_POINT");
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
 /// Errors generated when converting between relayer and smart contract types
 #[derive(Clone, Debug)]
 pub enum ConversionError {
@@ -7,3 +9,135 @@ pub enum ConversionError {
     /// Error thrown when converting between uint types
     InvalidUint,
 }
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            ConversionError::InvalidLength => write!(f, "invalid length"),
+            ConversionError::InvalidUint => write!(f, "invalid uint"),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// The error type returned by the helpers and exports of this crate
+///
+/// Each variant carries a stable code (see [`UtilsError::code`]) that is
+/// prefixed to the error message, so that callers on the JS side may match
+/// on it
+#[derive(Clone, Debug)]
+pub enum UtilsError {
+    /// Error thrown when converting between relayer and smart contract types
+    Conversion(ConversionError),
+    /// Error thrown when a string is not valid hex
+    InvalidHex(String),
+    /// Error thrown when a secret key is zero or exceeds the curve order
+    KeyOutOfRange(String),
+    /// Error thrown when a wallet has an unexpected number of secret shares
+    InvalidShareCount(String),
+    /// Error thrown when a wallet's contents exceed its capacity
+    InvalidWallet(String),
+    /// Error thrown when bytes do not encode a valid curve point
+    InvalidPoint(String),
+    /// Error thrown when (de)serializing a value fails
+    Serde(String),
+    /// Error thrown when generating a signature fails
+    Signing(String),
+    /// Error thrown when a signature does not verify under the given key
+    InvalidSignature(String),
+    /// Error thrown when a signature is verified after its expiration
+    SignatureExpired(String),
+    /// Error thrown when an HTTP request cannot be canonicalized for signing
+    InvalidHttpRequest(String),
+    /// Error thrown when sampling randomness from the environment fails
+    Randomness(String),
+    /// Error thrown when a Merkle opening is malformed or does not open to the given root
+    InvalidMerkleProof(String),
+    /// Error thrown when a leaf index does not fit in the Merkle tree
+    MerkleIndexOutOfRange(String),
+    /// Error thrown when a network identifier is not recognized
+    InvalidNetwork(String),
+    /// Error thrown when a key encoding is not recognized
+    InvalidEncoding(String),
+    /// Error thrown when a keystore is malformed or uses unsupported parameters
+    Keystore(String),
+    /// Error thrown when a keystore's MAC does not match, usually due to a wrong password
+    InvalidPassword(String),
+    /// Error thrown when a mnemonic has an unknown word, a bad checksum or the wrong length
+    InvalidMnemonic(String),
+    /// Error thrown when a key handle is unknown or no more handles may be issued
+    InvalidKeyHandle(String),
+    /// Error thrown when a Permit2 permit cannot authorize the given transfer
+    InvalidPermit(String),
+    /// Error thrown when more accounts are requested than may be enumerated at once
+    InvalidAccountCount(String),
+}
+
+impl UtilsError {
+    /// Returns the stable code identifying the error variant
+    pub fn code(&self) -> &'static str {
+        match self {
+            UtilsError::Conversion(_) => "CONVERSION_ERROR",
+            UtilsError::InvalidHex(_) => "INVALID_HEX",
+            UtilsError::KeyOutOfRange(_) => "KEY_OUT_OF_RANGE",
+            UtilsError::InvalidShareCount(_) => "INVALID_SHARE_COUNT",
+            UtilsError::InvalidWallet(_) => "INVALID_WALLET",
+            UtilsError::InvalidPoint(_) => "INVALID_POINT",
+            UtilsError::Serde(_) => "SERDE_ERROR",
+            UtilsError::Signing(_) => "SIGNING_ERROR",
+            UtilsError::InvalidSignature(_) => "INVALID_SIGNATURE",
+            UtilsError::SignatureExpired(_) => "SIGNATURE_EXPIRED",
+            UtilsError::InvalidHttpRequest(_) => "INVALID_HTTP_REQUEST",
+            UtilsError::Randomness(_) => "RANDOMNESS_ERROR",
+            UtilsError::InvalidMerkleProof(_) => "INVALID_MERKLE_PROOF",
+            UtilsError::MerkleIndexOutOfRange(_) => "MERKLE_INDEX_OUT_OF_RANGE",
+            UtilsError::InvalidNetwork(_) => "INVALID_NETWORK",
+            UtilsError::InvalidEncoding(_) => "INVALID_ENCODING",
+            UtilsError::Keystore(_) => "KEYSTORE_ERROR",
+            UtilsError::InvalidPassword(_) => "INVALID_PASSWORD",
+            UtilsError::InvalidMnemonic(_) => "INVALID_MNEMONIC",
+            UtilsError::InvalidKeyHandle(_) => "INVALID_KEY_HANDLE",
+            UtilsError::InvalidPermit(_) => "INVALID_PERMIT",
+            UtilsError::InvalidAccountCount(_) => "INVALID_ACCOUNT_COUNT",
+        }
+    }
+}
+
+impl Display for UtilsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let code = self.code();
+        match self {
+            UtilsError::Conversion(e) => write!(f, "{code}: {e}"),
+            UtilsError::InvalidHex(msg)
+            | UtilsError::KeyOutOfRange(msg)
+            | UtilsError::InvalidShareCount(msg)
+            | UtilsError::InvalidWallet(msg)
+            | UtilsError::InvalidPoint(msg)
+            | UtilsError::Serde(msg)
+            | UtilsError::Signing(msg)
+            | UtilsError::InvalidSignature(msg)
+            | UtilsError::SignatureExpired(msg)
+            | UtilsError::InvalidHttpRequest(msg)
+            | UtilsError::Randomness(msg)
+            | UtilsError::InvalidMerkleProof(msg)
+            | UtilsError::MerkleIndexOutOfRange(msg)
+            | UtilsError::InvalidNetwork(msg)
+            | UtilsError::InvalidEncoding(msg)
+            | UtilsError::Keystore(msg)
+            | UtilsError::InvalidPassword(msg)
+            | UtilsError::InvalidMnemonic(msg)
+            | UtilsError::InvalidKeyHandle(msg)
+            | UtilsError::InvalidPermit(msg)
+            | UtilsError::InvalidAccountCount(msg) => write!(f, "{code}: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for UtilsError {}
+
+impl From<ConversionError> for UtilsError {
+    fn from(e: ConversionError) -> Self {
+        UtilsError::Conversion(e)
+    }
+}
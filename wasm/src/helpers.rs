@@ -1,40 +1,54 @@
 use crate::{
-    errors::ConversionError,
+    errors::{ConversionError, UtilsError},
+    keys::{derive_match_key, Network},
     types::{
         ApiWallet, BabyJubJubPoint, ContractExternalTransfer, EmbeddedCurveConfig,
-        ExternalTransfer, ExternalTransferDirection, PublicIdentificationKey, ScalarField,
-        SecretIdentificationKey, Wallet,
+        EmbeddedScalarField, ExternalTransfer, ExternalTransferDirection, HttpRequest,
+        PublicIdentificationKey, ScalarField, SecretIdentificationKey, WalletShares,
     },
 };
 use alloy_primitives::Address;
 use ark_ec::twisted_edwards::Projective;
 use ark_ff::PrimeField;
-use ark_serialize::CanonicalDeserialize;
-use k256::ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ethers::{types::H160, utils::to_checksum};
+use k256::ecdsa::{SigningKey, VerifyingKey};
 use num_bigint::BigUint;
 use num_traits::Num;
 use renegade_crypto::hash::Poseidon2Sponge;
 use ruint::aliases::{U160, U256};
-use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serializer};
+use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serialize, Serializer};
 use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
 
-const CREATE_SK_MATCH_MESSAGE: &str = "Unlock your Renegade match key.\nTestnet v0";
+/// The number of bytes in a secp256k1 scalar
+pub const SECP256K1_SCALAR_BYTES: usize = 32;
+/// The number of bytes in an Ethereum address
+pub const ADDRESS_BYTES: usize = 20;
 
 // -----------------------------------
 // | Wallet Update Signature Helpers |
 // -----------------------------------
 
-/// Deserializes a JSON string into a `Wallet` object.
-pub fn deserialize_wallet(wallet_str: &str) -> Wallet {
+/// Deserializes a JSON string into a `WalletShares` object.
+pub fn deserialize_wallet(wallet_str: &str) -> Result<WalletShares, UtilsError> {
     let wallet_bytes = wallet_str.as_bytes();
-    let deserialized_wallet: ApiWallet = serde_json::from_reader(wallet_bytes).unwrap();
-    deserialized_wallet.try_into().unwrap()
+    let deserialized_wallet: ApiWallet = serde_json::from_reader(wallet_bytes)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing wallet: {e}")))?;
+    deserialized_wallet.try_into()
 }
 
-pub fn deserialize_external_transfer(transfer_str: &str) -> ExternalTransfer {
+/// Deserializes a JSON string into an `ExternalTransfer` object.
+pub fn deserialize_external_transfer(transfer_str: &str) -> Result<ExternalTransfer, UtilsError> {
     let transfer_bytes = transfer_str.as_bytes();
-    let deserialized_transfer: ExternalTransfer = serde_json::from_reader(transfer_bytes).unwrap();
-    deserialized_transfer
+    serde_json::from_reader(transfer_bytes)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing external transfer: {e}")))
+}
+
+/// Deserializes a JSON string into an `HttpRequest` object.
+pub fn deserialize_http_request(request_str: &str) -> Result<HttpRequest, UtilsError> {
+    serde_json::from_str(request_str)
+        .map_err(|e| UtilsError::Serde(format!("error deserializing http request: {e}")))
 }
 
 /// Convert a BigUint to a scalar
@@ -55,6 +69,11 @@ pub fn point_coord_to_string(coord_bytes: &[u8]) -> Vec<String> {
         .collect::<Vec<String>>()
 }
 
+/// Converts a point coordinate to its scalar field words, in little endian order
+pub fn point_coord_to_scalars(coord_bytes: &[u8]) -> [ScalarField; 2] {
+    split_biguint_into_words(BigUint::from_bytes_be(coord_bytes))
+}
+
 // -----------
 // | Helpers |
 // -----------
@@ -66,11 +85,16 @@ pub fn _compute_poseidon_hash(inputs: &[ScalarField]) -> ScalarField {
 }
 
 /// A helper to deserialize a BigUint from a hex string
-pub fn biguint_from_hex_string(hex: &str) -> Result<BigUint, String> {
+pub fn biguint_from_hex_string(hex: &str) -> Result<BigUint, UtilsError> {
     // Deserialize as a string and remove "0x" if present
     let stripped = hex.strip_prefix("0x").unwrap_or(hex);
     BigUint::from_str_radix(stripped, 16 /* radix */)
-        .map_err(|e| format!("error deserializing BigUint from hex string: {e}"))
+        .map_err(|e| UtilsError::InvalidHex(format!("error deserializing BigUint: {e}")))
+}
+
+/// A helper to deserialize a scalar from a hex string, reducing it into the field
+pub fn scalar_from_hex_string(hex: &str) -> Result<ScalarField, UtilsError> {
+    biguint_from_hex_string(hex).map(ScalarField::from)
 }
 
 /// A helper to serialize a BigUint to a hex string
@@ -109,21 +133,53 @@ pub fn get_scalar_field_modulus() -> BigUint {
 }
 
 /// Generates a `SecretIdentificationKey` and a `PublicIdentificationKey` from a given `SigningKey`.
-pub fn get_match_key(sk_root: SigningKey) -> (SecretIdentificationKey, PublicIdentificationKey) {
-    let sig: Signature = sk_root.sign(CREATE_SK_MATCH_MESSAGE.as_bytes());
-    let message_hash = compute_sha256_hash(&sig.to_bytes());
-    let sk_match =
-        SecretIdentificationKey::from(ScalarField::from(BigUint::from_bytes_be(&message_hash)));
-    let pk_match = sk_match.get_public_key();
-    (sk_match, pk_match)
+///
+/// Uses the given network's match key message, see [`derive_match_key`]
+pub fn get_match_key(
+    sk_root: SigningKey,
+    network: Network,
+) -> (SecretIdentificationKey, PublicIdentificationKey) {
+    derive_match_key(&sk_root, network)
 }
 
 /// Return a `SigningKey` and a `Verifying` from a hex string
-pub fn get_root_key(key: &str) -> (SigningKey, VerifyingKey) {
-    let key_bigint = biguint_from_hex_string(key).unwrap();
-    let signing_key = SigningKey::from_slice(&key_bigint.to_bytes_be()).unwrap();
-    let verifying_key = signing_key.clone().verifying_key().to_owned();
-    (signing_key, verifying_key)
+///
+/// The decoded key bytes are wiped once parsed, and the returned `SigningKey`
+/// zeroizes itself when dropped
+pub fn get_root_key(key: &str) -> Result<(SigningKey, VerifyingKey), UtilsError> {
+    let stripped = key.strip_prefix("0x").unwrap_or(key);
+    if stripped.is_empty() || !stripped.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(UtilsError::InvalidHex(
+            "sk_root must be a non-empty hex string".to_string(),
+        ));
+    }
+
+    let digits = stripped.trim_start_matches('0');
+    let max_digits = 2 * SECP256K1_SCALAR_BYTES;
+    if digits.len() > max_digits {
+        return Err(UtilsError::KeyOutOfRange(format!(
+            "sk_root must be at most {SECP256K1_SCALAR_BYTES} bytes, got {}",
+            digits.len().div_ceil(2)
+        )));
+    }
+
+    // Left-pad to the full width of a secp256k1 scalar
+    let padded_hex = Zeroizing::new(format!("{digits:0>max_digits$}"));
+    let mut padded = Zeroizing::new([0u8; SECP256K1_SCALAR_BYTES]);
+    hex::decode_to_slice(&*padded_hex, &mut *padded)
+        .map_err(|e| UtilsError::InvalidHex(format!("error deserializing sk_root: {e}")))?;
+    let signing_key = SigningKey::from_slice(&*padded)
+        .map_err(|e| UtilsError::KeyOutOfRange(format!("invalid sk_root: {e}")))?;
+    let verifying_key = *signing_key.verifying_key();
+    Ok((signing_key, verifying_key))
+}
+
+/// Parse `pk_root` from the hex encoding of its SEC1 representation, either
+/// compressed or uncompressed
+pub fn pk_root_from_hex_string(hex: &str) -> Result<VerifyingKey, UtilsError> {
+    let bytes = bytes_from_hex_string(hex)?;
+    VerifyingKey::from_sec1_bytes(&bytes)
+        .map_err(|e| UtilsError::InvalidPoint(format!("invalid pk_root: {e}")))
 }
 
 /// Split a biguint into scalar words in little endian order
@@ -138,18 +194,165 @@ pub fn split_biguint_into_words(mut val: BigUint) -> [ScalarField; 2] {
     res.try_into().unwrap()
 }
 
-/// Deserialize a Baby-JubJub point from a hex string
-pub fn jubjub_from_hex_string(hex: &str) -> Result<BabyJubJubPoint, String> {
-    // Deserialize as a string and remove "0x" if present
-    let stripped = hex.strip_prefix("0x").unwrap_or(hex);
-    let bytes = hex::decode(stripped)
-        .map_err(|e| format!("error deserializing bytes from hex string: {e}"))?;
+/// Combine scalar words in little endian order into a biguint, the inverse of
+/// `split_biguint_into_words`
+pub fn combine_words_into_biguint(words: &[ScalarField]) -> BigUint {
+    let scalar_mod = get_scalar_field_modulus();
+    words.iter().rev().fold(BigUint::from(0u8), |acc, word| {
+        let word_bigint: BigUint = (*word).into();
+        acc * &scalar_mod + word_bigint
+    })
+}
+
+/// Convert a scalar into a `u128`, failing if the scalar does not fit
+pub fn scalar_to_u128(scalar: &ScalarField) -> Result<u128, UtilsError> {
+    let bigint: BigUint = (*scalar).into();
+    bigint
+        .try_into()
+        .map_err(|_| UtilsError::Conversion(ConversionError::InvalidUint))
+}
+
+/// A helper to serialize a scalar to a hex string
+pub fn serialize_scalar_to_hex_string<S>(
+    val: &ScalarField,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_biguint_to_hex_string(&(*val).into(), serializer)
+}
+
+/// The number of bytes in the canonical encoding of a scalar
+const SCALAR_BYTES: usize = 32;
+
+/// A helper to serialize a scalar to a fixed-width, 0x-prefixed hex string,
+/// padding it to the full width of the field
+pub fn scalar_to_fixed_hex_string(val: &ScalarField) -> String {
+    let bigint: BigUint = (*val).into();
+    format!(
+        "0x{:0>width$}",
+        bigint.to_str_radix(16 /* radix */),
+        width = 2 * SCALAR_BYTES
+    )
+}
+
+/// A helper to serialize a scalar to a fixed-width hex string
+pub fn serialize_scalar_to_fixed_hex_string<S>(
+    val: &ScalarField,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&scalar_to_fixed_hex_string(val))
+}
+
+/// A helper to deserialize a scalar from a hex string
+pub fn deserialize_scalar_from_hex_string<'de, D>(deserializer: D) -> Result<ScalarField, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = String::deserialize(deserializer)?;
+    scalar_from_hex_string(&hex).map_err(D::Error::custom)
+}
+
+/// A helper to serialize a vector of scalars to hex strings
+pub fn serialize_scalars_to_hex_strings<S>(
+    vals: &[ScalarField],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let hex: Vec<String> = vals
+        .iter()
+        .map(|val| biguint_to_hex_string(&(*val).into()))
+        .collect();
+    hex.serialize(serializer)
+}
 
+/// A helper to deserialize a vector of scalars from hex strings
+pub fn deserialize_scalars_from_hex_strings<'de, D>(
+    deserializer: D,
+) -> Result<Vec<ScalarField>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hex = Vec::<String>::deserialize(deserializer)?;
+    hex.iter()
+        .map(|val| scalar_from_hex_string(val).map_err(D::Error::custom))
+        .collect()
+}
+
+/// Deserialize a Baby-JubJub point from a hex string
+pub fn jubjub_from_hex_string(hex: &str) -> Result<BabyJubJubPoint, UtilsError> {
+    let bytes = bytes_from_hex_string(hex)?;
     let projective = Projective::<EmbeddedCurveConfig>::deserialize_uncompressed(bytes.as_slice())
-        .map_err(|e| format!("error deserializing projective point from bytes: {:?}", e))?;
+        .map_err(|e| {
+            UtilsError::InvalidPoint(format!("error deserializing projective point: {e:?}"))
+        })?;
     Ok(projective.into())
 }
 
+/// Serialize a Baby-JubJub point to a hex string, the inverse of
+/// `jubjub_from_hex_string`
+pub fn jubjub_to_hex_string(point: &BabyJubJubPoint) -> Result<String, UtilsError> {
+    let projective = Projective::<EmbeddedCurveConfig>::try_from(*point)?;
+    let mut bytes = Vec::new();
+    projective
+        .serialize_uncompressed(&mut bytes)
+        .map_err(|e| UtilsError::InvalidPoint(format!("error serializing point: {e:?}")))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Deserialize a scalar of the Baby-JubJub curve's scalar field from a hex
+/// string, failing if it is not a canonical field element
+pub fn embedded_scalar_from_hex_string(hex: &str) -> Result<EmbeddedScalarField, UtilsError> {
+    let bigint = biguint_from_hex_string(hex)?;
+    if bigint >= EmbeddedScalarField::MODULUS.into() {
+        return Err(UtilsError::KeyOutOfRange(
+            "scalar exceeds the Baby-JubJub group order".to_string(),
+        ));
+    }
+    Ok(EmbeddedScalarField::from(bigint))
+}
+
+/// Serialize a scalar of the Baby-JubJub curve's scalar field to a hex string
+pub fn embedded_scalar_to_hex_string(scalar: &EmbeddedScalarField) -> String {
+    biguint_to_hex_string(&(*scalar).into())
+}
+
+/// A helper to deserialize a byte vector from a hex string
+pub fn bytes_from_hex_string(hex: &str) -> Result<Vec<u8>, UtilsError> {
+    // Remove "0x" if present
+    let stripped = hex.strip_prefix("0x").unwrap_or(hex);
+    hex::decode(stripped)
+        .map_err(|e| UtilsError::InvalidHex(format!("error deserializing bytes: {e}")))
+}
+
+/// Parse an Ethereum address from a hex string
+pub fn address_from_hex_string(hex: &str) -> Result<Address, UtilsError> {
+    let bytes = bytes_from_hex_string(hex)?;
+    if bytes.len() != ADDRESS_BYTES {
+        return Err(UtilsError::InvalidHex(format!(
+            "address must be {ADDRESS_BYTES} bytes, got {}",
+            bytes.len()
+        )));
+    }
+
+    Ok(Address::from_slice(&bytes))
+}
+
+/// Format an Ethereum address as a hex string with the EIP-55 checksum
+/// casing
+pub fn address_to_checksum_hex_string(address: &Address) -> String {
+    to_checksum(
+        &H160::from_slice(address.as_slice()),
+        None, /* chain_id */
+    )
+}
+
 /// Convert an [`ExternalTransfer`] to its corresponding smart contract type
 pub fn to_contract_external_transfer(
     external_transfer: &ExternalTransfer,
@@ -176,3 +379,19 @@ pub fn to_contract_external_transfer(
         is_withdrawal: external_transfer.direction == ExternalTransferDirection::Withdrawal,
     })
 }
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    //! Fixtures shared by the tests of the crate
+
+    use super::get_root_key;
+    use k256::ecdsa::{SigningKey, VerifyingKey};
+
+    /// The `sk_root` of the mock wallet, in hex
+    const MOCK_SK_ROOT: &str = "05fb4b6c5af30b21e240d6c162d33599856bcb8c8489bc344da554ce96aa2a2a";
+
+    /// Get the `sk_root` and `pk_root` of the mock wallet
+    pub fn mock_sk_root() -> (SigningKey, VerifyingKey) {
+        get_root_key(MOCK_SK_ROOT).unwrap()
+    }
+}
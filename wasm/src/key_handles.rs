@@ -0,0 +1,98 @@
+//! A store of `sk_root` keys referenced by opaque handles
+//!
+//! Keys enter the store once, on import or derivation, and are referenced by
+//! their [`KeyHandle`] afterwards, so that callers need not hold the secret.
+//! The stored [`SigningKey`]s zeroize their secret scalar when dropped, i.e.
+//! when released from the store or when the store itself is dropped
+//!
+//! Each key is boxed, so that it sits at a fixed heap address for as long as
+//! it is stored. When the map grows it moves only the pointers, rather than
+//! leaving unwiped copies of the keys in the memory of its old table
+
+use crate::errors::UtilsError;
+use ethers::core::k256::ecdsa::SigningKey;
+use std::collections::HashMap;
+
+/// An opaque handle to a key in a [`KeyHandleStore`]
+pub type KeyHandle = u32;
+
+/// A store of signing keys referenced by opaque handles
+///
+/// Handles are never reused, so a stale handle cannot refer to a key inserted
+/// after its own was released
+#[derive(Default)]
+pub struct KeyHandleStore {
+    /// The stored keys, indexed by their handle
+    keys: HashMap<KeyHandle, Box<SigningKey>>,
+    /// The handle to issue to the next inserted key
+    next_handle: KeyHandle,
+}
+
+impl KeyHandleStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert a key into the store, returning its handle
+    pub fn insert(&mut self, key: SigningKey) -> Result<KeyHandle, UtilsError> {
+        let handle = self.next_handle;
+        self.next_handle = handle
+            .checked_add(1)
+            .ok_or_else(|| UtilsError::InvalidKeyHandle("key handles exhausted".to_string()))?;
+
+        self.keys.insert(handle, Box::new(key));
+        Ok(handle)
+    }
+
+    /// Get the key referenced by the given handle
+    pub fn get(&self, handle: KeyHandle) -> Result<&SigningKey, UtilsError> {
+        self.keys
+            .get(&handle)
+            .map(Box::as_ref)
+            .ok_or_else(|| UtilsError::InvalidKeyHandle(format!("unknown key handle: {handle}")))
+    }
+
+    /// Export a copy of the key referenced by the given handle
+    ///
+    /// The key remains in the store until released
+    pub fn export(&self, handle: KeyHandle) -> Result<SigningKey, UtilsError> {
+        self.get(handle).cloned()
+    }
+
+    /// Release the key referenced by the given handle, zeroizing it
+    pub fn release(&mut self, handle: KeyHandle) -> Result<(), UtilsError> {
+        self.keys
+            .remove(&handle)
+            .map(drop)
+            .ok_or_else(|| UtilsError::InvalidKeyHandle(format!("unknown key handle: {handle}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::get_root_key;
+
+    #[test]
+    fn test_key_handles() {
+        let (key_one, _) = get_root_key("0x1").unwrap();
+        let (key_two, _) = get_root_key("0x2").unwrap();
+
+        let mut store = KeyHandleStore::new();
+        let handle_one = store.insert(key_one.clone()).unwrap();
+        let handle_two = store.insert(key_two.clone()).unwrap();
+        assert_ne!(handle_one, handle_two);
+        assert_eq!(store.get(handle_one).unwrap(), &key_one);
+        assert_eq!(store.export(handle_two).unwrap(), key_two);
+
+        // A released handle is not reissued to a new key
+        store.release(handle_one).unwrap();
+        let err = store.get(handle_one).unwrap_err();
+        assert_eq!(err.code(), "INVALID_KEY_HANDLE");
+        assert!(store.release(handle_one).is_err());
+
+        let handle_three = store.insert(key_one).unwrap();
+        assert_ne!(handle_three, handle_one);
+    }
+}
@@ -0,0 +1,727 @@
+use crate::errors::UtilsError;
+use crate::helpers::{
+    bytes_from_hex_string, combine_words_into_biguint, compute_sha256_hash,
+    deserialize_scalar_from_hex_string, point_coord_to_scalars, scalar_to_fixed_hex_string,
+    serialize_scalar_to_fixed_hex_string, ADDRESS_BYTES,
+};
+use crate::signature::personal_sign_hash;
+use crate::signer::{Signer as _, LEGACY_V_OFFSET};
+use crate::types::{PublicIdentificationKey, ScalarField, SecretIdentificationKey};
+use crate::wallet::Keychain;
+use alloy_primitives::Address;
+use ark_ff::PrimeField;
+use ethers::utils::keccak256;
+use k256::ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey};
+use k256::elliptic_curve::{bigint::U512, ops::Reduce, sec1::ToEncodedPoint};
+use k256::{NonZeroScalar, Scalar, WideBytes};
+use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+    str::FromStr,
+};
+use uuid::{Builder as UuidBuilder, Uuid};
+use zeroize::{Zeroize, Zeroizing};
+
+/// The number of scalar words used to represent `pk_root`
+///
+/// Stored as the affine coordinates of the point, each split into two words
+pub const NUM_ROOT_KEY_WORDS: usize = 4;
+/// The number of shares used to represent the public keys of the key
+/// hierarchy, `pk_root` plus one for `pk_match`
+pub const NUM_KEY_HIERARCHY_SHARES: usize = NUM_ROOT_KEY_WORDS + 1;
+
+/// The number of bytes of the `pk_root` hash used as the wallet ID's randomness
+const WALLET_ID_BYTES: usize = 16;
+
+/// The number of bytes in a secp256k1 base field element
+const SECP256K1_COORD_BYTES: usize = 32;
+/// The SEC1 tag prefixing an uncompressed point
+const SEC1_UNCOMPRESSED_TAG: u8 = 0x04;
+
+/// The prefix of the message signed by the user's Ethereum wallet to derive
+/// `sk_root`
+const ROOT_KEY_MESSAGE_PREFIX: &str = "Unlock your Renegade account.";
+/// The prefix of the message signed with `sk_root` to derive `sk_match`
+const MATCH_KEY_MESSAGE_PREFIX: &str = "Unlock your Renegade match key.";
+/// The domain separator of the blinder seed derivation
+const BLINDER_SEED_DOMAIN: &str = "Renegade blinder seed";
+/// The domain separator of the secret share seed derivation
+const SHARE_SEED_DOMAIN: &str = "Renegade share seed";
+/// The offset of the legacy testnet blinder seed from `sk_root`
+const LEGACY_BLINDER_SEED_OFFSET: u64 = 1;
+/// The offset of the legacy testnet secret share seed from `sk_root`
+const LEGACY_SHARE_SEED_OFFSET: u64 = 2;
+/// The domain separator of the root key derivation of indexed accounts
+const ACCOUNT_ROOT_KEY_DOMAIN: &str = "Renegade account root key";
+/// The maximum number of accounts whose IDs may be enumerated in one call
+pub const MAX_ACCOUNT_IDS: u32 = 256;
+
+// ------------------
+// | Key Derivation |
+// ------------------
+
+/// The network a key hierarchy is derived for
+///
+/// The secrets below `sk_root` are bound to the network's version tag, so
+/// they never collide across networks. `sk_root` itself, and so the wallet
+/// ID, is derived as it was before networks were distinguished and is bound
+/// to the network only by the message signed: the same signature derives the
+/// same `sk_root` on every network
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Network {
+    /// The production deployment
+    Mainnet,
+    /// The test deployment, which all keys were derived for before networks
+    /// were distinguished
+    #[default]
+    Testnet,
+}
+
+impl Network {
+    /// The version tag appended to each message signed to derive keys
+    pub fn version_tag(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "Mainnet v0",
+            Network::Testnet => "Testnet v0",
+        }
+    }
+
+    /// The message the user's Ethereum wallet signs to derive `sk_root`
+    pub fn root_key_message(&self) -> String {
+        format!("{ROOT_KEY_MESSAGE_PREFIX}\n{}", self.version_tag())
+    }
+
+    /// The message signed with `sk_root` to derive `sk_match`
+    pub fn match_key_message(&self) -> String {
+        format!("{MATCH_KEY_MESSAGE_PREFIX}\n{}", self.version_tag())
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = UtilsError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(UtilsError::InvalidNetwork(format!("unknown network: {s}"))),
+        }
+    }
+}
+
+/// The secret keys and seeds of a wallet, all derived from a single signature
+pub struct DerivedKeys {
+    /// The root key, authorizes updates to the wallet
+    pub sk_root: SigningKey,
+    /// The match key, authorizes matches on the wallet's orders
+    pub sk_match: SecretIdentificationKey,
+    /// The seed of the wallet's blinder stream
+    ///
+    /// On testnet this is the TS client's legacy `sk_root + 1` seed, so both
+    /// clients derive the same shares for existing wallets
+    pub blinder_seed: ScalarField,
+    /// The seed of the wallet's secret share stream
+    ///
+    /// On testnet this is the TS client's legacy `sk_root + 2` seed
+    pub share_seed: ScalarField,
+}
+
+impl Debug for DerivedKeys {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("DerivedKeys(<redacted>)")
+    }
+}
+
+impl Drop for DerivedKeys {
+    // `sk_root` and `sk_match` zeroize themselves when dropped
+    fn drop(&mut self) {
+        self.blinder_seed.zeroize();
+        self.share_seed.zeroize();
+    }
+}
+
+/// Derive the full key hierarchy of a wallet from the user's signature over
+/// [`Network::root_key_message`]
+///
+/// `sk_root` is derived from the signature as in [`derive_signing_key`],
+/// ignoring `network` so that existing wallets keep their keys and IDs; it is
+/// bound to the network only by the message signed. `sk_match` is derived
+/// from `sk_root` under the network's version tag. The seeds are derived
+/// under their own domain separator and the version tag, except on testnet,
+/// where they keep the TS client's legacy derivation, see
+/// [`DerivedKeys::blinder_seed`]
+pub fn derive_key_hierarchy(signature: &[u8], network: Network) -> Result<DerivedKeys, UtilsError> {
+    let sk_root = derive_signing_key(signature)?;
+    Ok(derive_keys_from_sk_root(sk_root, network))
+}
+
+/// Derive the key hierarchy of the account at the given index from the
+/// user's signature over [`Network::root_key_message`]
+///
+/// Every account other than account zero derives its `sk_root` from the
+/// signature under a domain separator bound to the network and the index, so
+/// each account may be recovered from the signature and its index alone.
+///
+/// Account zero is the legacy account of [`derive_key_hierarchy`], whose
+/// `sk_root` ignores both the network and the index. It is therefore the only
+/// account whose `sk_root` and wallet ID are the same on every network for a
+/// given signature
+pub fn derive_account_key_hierarchy(
+    signature: &[u8],
+    network: Network,
+    index: u32,
+) -> Result<DerivedKeys, UtilsError> {
+    let sk_root = derive_account_sk_root(signature, network, index)?;
+    Ok(derive_keys_from_sk_root(sk_root, network))
+}
+
+/// Sign the network's root key message with an Ethereum private key, as an
+/// Ethereum wallet does when the web app requests it with `personal_sign`
+///
+/// The signature is returned in the wallet's `r || s || v` encoding, with `v`
+/// in `{27, 28}`, so that it derives the same keys as the web app. It is as
+/// sensitive as the keys it derives, and is wiped when dropped
+pub fn sign_root_key_message(
+    eth_key: &SigningKey,
+    network: Network,
+) -> Result<Zeroizing<Vec<u8>>, UtilsError> {
+    let message_hash = personal_sign_hash(network.root_key_message().as_bytes());
+    let mut sig = eth_key.sign_prehash(&message_hash)?;
+    sig.v += LEGACY_V_OFFSET;
+    Ok(Zeroizing::new(sig.to_vec()))
+}
+
+/// Derive the full key hierarchy of a wallet from the Ethereum private key
+/// that owns it, as the web app does from the key's wallet
+pub fn derive_key_hierarchy_from_eth_key(
+    eth_key: &SigningKey,
+    network: Network,
+) -> Result<DerivedKeys, UtilsError> {
+    let signature = sign_root_key_message(eth_key, network)?;
+    derive_key_hierarchy(&signature, network)
+}
+
+/// Enumerate the wallet IDs of the first `count` accounts derived from the
+/// user's signature, in index order
+///
+/// At most [`MAX_ACCOUNT_IDS`] accounts may be enumerated at once
+pub fn derive_account_ids(
+    signature: &[u8],
+    network: Network,
+    count: u32,
+) -> Result<Vec<Uuid>, UtilsError> {
+    if count > MAX_ACCOUNT_IDS {
+        return Err(UtilsError::InvalidAccountCount(format!(
+            "cannot enumerate {count} accounts, at most {MAX_ACCOUNT_IDS} may be enumerated"
+        )));
+    }
+
+    (0..count)
+        .map(|index| {
+            let sk_root = derive_account_sk_root(signature, network, index)?;
+            Ok(derive_wallet_id(sk_root.verifying_key()))
+        })
+        .collect()
+}
+
+/// Derive the `sk_root` of the account at the given index, see
+/// [`derive_account_key_hierarchy`]
+fn derive_account_sk_root(
+    signature: &[u8],
+    network: Network,
+    index: u32,
+) -> Result<SigningKey, UtilsError> {
+    if index == 0 {
+        return derive_signing_key(signature);
+    }
+
+    let label = format!(
+        "{ACCOUNT_ROOT_KEY_DOMAIN}\n{}\n{index}\n",
+        network.version_tag()
+    );
+    derive_signing_key(&[label.as_bytes(), signature].concat())
+}
+
+/// Derive the secrets below `sk_root` in the key hierarchy
+fn derive_keys_from_sk_root(sk_root: SigningKey, network: Network) -> DerivedKeys {
+    let (sk_match, _) = derive_match_key(&sk_root, network);
+    let (blinder_seed, share_seed) = match network {
+        Network::Testnet => (
+            derive_legacy_seed(&sk_root, LEGACY_BLINDER_SEED_OFFSET),
+            derive_legacy_seed(&sk_root, LEGACY_SHARE_SEED_OFFSET),
+        ),
+        Network::Mainnet => (
+            derive_seed(&sk_root, BLINDER_SEED_DOMAIN, network),
+            derive_seed(&sk_root, SHARE_SEED_DOMAIN, network),
+        ),
+    };
+
+    DerivedKeys {
+        sk_root,
+        sk_match,
+        blinder_seed,
+        share_seed,
+    }
+}
+
+/// Derive the match keypair from `sk_root` by signing the network's match key
+/// message
+pub fn derive_match_key(
+    sk_root: &SigningKey,
+    network: Network,
+) -> (SecretIdentificationKey, PublicIdentificationKey) {
+    let sig: Signature = sk_root.sign(network.match_key_message().as_bytes());
+    let sig_bytes = Zeroizing::new(sig.to_bytes());
+    let message_hash = Zeroizing::new(compute_sha256_hash(&sig_bytes));
+    let sk_match =
+        SecretIdentificationKey::from(ScalarField::from_be_bytes_mod_order(&message_hash));
+    let pk_match = sk_match.get_public_key();
+    (sk_match, pk_match)
+}
+
+/// Derive a seed from `sk_root` under the given domain separator and network
+fn derive_seed(sk_root: &SigningKey, domain: &str, network: Network) -> ScalarField {
+    let label = format!("{domain}\n{}\n", network.version_tag());
+    let sk_bytes = Zeroizing::new(sk_root.to_bytes());
+    let preimage = Zeroizing::new([label.as_bytes(), sk_bytes.as_slice()].concat());
+    let preimage_hash = Zeroizing::new(keccak256(&*preimage));
+    let seed_bytes = Zeroizing::new(extend_to_64_bytes(&*preimage_hash));
+    ScalarField::from_be_bytes_mod_order(&*seed_bytes)
+}
+
+/// Derive a legacy testnet seed, `sk_root` plus the given offset reduced into
+/// the scalar field, as the TS client does
+fn derive_legacy_seed(sk_root: &SigningKey, offset: u64) -> ScalarField {
+    let sk_bytes = Zeroizing::new(sk_root.to_bytes());
+    ScalarField::from_be_bytes_mod_order(&sk_bytes) + ScalarField::from(offset)
+}
+
+/// Derive a signing key from a signature on a message
+///
+/// The intermediate hashes of the signature are wiped once the key is derived
+pub fn derive_signing_key(msg: &[u8]) -> Result<SigningKey, UtilsError> {
+    let sig_bytes = get_extended_sig_bytes(msg);
+
+    // We must manually reduce the bytes to the scalar field as the k256 library
+    // expects byte representations to be of a valid scalar directly
+    let mut scalar = <Scalar as Reduce<U512>>::reduce_bytes(WideBytes::from_slice(&*sig_bytes));
+    let key = Option::<NonZeroScalar>::from(NonZeroScalar::new(scalar));
+    scalar.zeroize();
+
+    key.map(SigningKey::from).ok_or_else(|| {
+        UtilsError::KeyOutOfRange("signature derives the zero signing key".to_string())
+    })
+}
+
+// Hash and extend a signature to 64 bytes
+fn get_extended_sig_bytes(msg: &[u8]) -> Zeroizing<[u8; EXTENDED_BYTES]> {
+    // Take the keccak hash of the signature to disperse its elements
+    let keccak_bytes = Zeroizing::new(keccak256(msg));
+    Zeroizing::new(extend_to_64_bytes(&*keccak_bytes))
+}
+
+/// The number of bytes from a keccak hash
+const KECCAK_HASH_BYTES: usize = 32;
+/// The number of bytes we extend into to get a scalar
+const EXTENDED_BYTES: usize = 64;
+
+/// Extend the given byte array to 64 bytes, double the length of the original
+///
+/// This is necessary to give a uniform sampling of a field that these bytes are
+/// reduced into, the bitlength must be significantly larger than the field's
+/// bitlength to avoid sample bias via modular reduction
+fn extend_to_64_bytes(bytes: &[u8]) -> [u8; EXTENDED_BYTES] {
+    let mut extended = [0; EXTENDED_BYTES];
+    let top_bytes = Zeroizing::new(keccak256(bytes));
+    extended[..KECCAK_HASH_BYTES].copy_from_slice(bytes);
+    extended[KECCAK_HASH_BYTES..].copy_from_slice(&*top_bytes);
+    extended
+}
+
+/// Split `pk_root` into its scalar words, the affine x coordinate followed by
+/// the affine y coordinate, each in little endian word order
+pub fn pk_root_to_scalars(pk_root: &VerifyingKey) -> [ScalarField; NUM_ROOT_KEY_WORDS] {
+    let encoded_key = pk_root.as_affine().to_encoded_point(false /* compress */);
+    // A verifying key is never the identity, so both coordinates are present
+    let x_coord = point_coord_to_scalars(encoded_key.x().expect("pk_root has an x coordinate"));
+    let y_coord = point_coord_to_scalars(encoded_key.y().expect("pk_root has a y coordinate"));
+    [x_coord[0], x_coord[1], y_coord[0], y_coord[1]]
+}
+
+/// Reconstruct `pk_root` from its scalar words, the inverse of `pk_root_to_scalars`
+pub fn pk_root_from_scalars(
+    words: &[ScalarField; NUM_ROOT_KEY_WORDS],
+) -> Result<VerifyingKey, UtilsError> {
+    let mut encoded_key = [0u8; 1 + 2 * SECP256K1_COORD_BYTES];
+    encoded_key[0] = SEC1_UNCOMPRESSED_TAG;
+    for (i, coord_words) in words.chunks(2).enumerate() {
+        let coord_bytes = combine_words_into_biguint(coord_words).to_bytes_be();
+        if coord_bytes.len() > SECP256K1_COORD_BYTES {
+            return Err(UtilsError::InvalidPoint(
+                "pk_root coordinate exceeds the base field".to_string(),
+            ));
+        }
+
+        let end = 1 + (i + 1) * SECP256K1_COORD_BYTES;
+        encoded_key[end - coord_bytes.len()..end].copy_from_slice(&coord_bytes);
+    }
+
+    VerifyingKey::from_sec1_bytes(&encoded_key)
+        .map_err(|e| UtilsError::InvalidPoint(format!("invalid pk_root: {e}")))
+}
+
+// -----------------
+// | Key Hierarchy |
+// -----------------
+
+/// The secret keys of a wallet's key hierarchy
+#[derive(Serialize, Deserialize)]
+pub struct PrivateKeychain {
+    /// The secret root key, authorizes updates to the wallet
+    #[serde(
+        serialize_with = "serialize_sk_root",
+        deserialize_with = "deserialize_sk_root"
+    )]
+    pub sk_root: SigningKey,
+    /// The secret match key, authorizes matches on the wallet's orders
+    #[serde(
+        serialize_with = "serialize_sk_match",
+        deserialize_with = "deserialize_sk_match"
+    )]
+    pub sk_match: SecretIdentificationKey,
+}
+
+/// The full key hierarchy of a wallet
+///
+/// Serializes every key as a fixed-width, 0x-prefixed hex string, with
+/// `pk_root` in its uncompressed SEC1 form
+#[derive(Serialize, Deserialize)]
+pub struct KeyHierarchy {
+    /// The public keys, as committed to in the wallet
+    pub public_keys: Keychain,
+    /// The secret keys
+    pub private_keys: PrivateKeychain,
+}
+
+impl KeyHierarchy {
+    /// Derive the key hierarchy of the given network from `sk_root`
+    pub fn from_sk_root(sk_root: &SigningKey, network: Network) -> Self {
+        let (sk_match, pk_match) = derive_match_key(sk_root, network);
+        Self {
+            public_keys: Keychain {
+                pk_root: *sk_root.verifying_key(),
+                pk_match,
+            },
+            private_keys: PrivateKeychain {
+                sk_root: sk_root.clone(),
+                sk_match,
+            },
+        }
+    }
+}
+
+/// Serialize `sk_root` as the hex encoding of its big endian bytes
+fn serialize_sk_root<S: Serializer>(
+    sk_root: &SigningKey,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", hex::encode(sk_root.to_bytes())))
+}
+
+/// Deserialize `sk_root` from the hex encoding of its big endian bytes
+fn deserialize_sk_root<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SigningKey, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    let bytes = bytes_from_hex_string(&hex).map_err(D::Error::custom)?;
+    SigningKey::from_slice(&bytes).map_err(|e| D::Error::custom(format!("invalid sk_root: {e}")))
+}
+
+/// Serialize `sk_match` as the fixed-width hex encoding of its key
+fn serialize_sk_match<S: Serializer>(
+    sk_match: &SecretIdentificationKey,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_scalar_to_fixed_hex_string(sk_match.key(), serializer)
+}
+
+/// Deserialize `sk_match` from the hex encoding of its key
+fn deserialize_sk_match<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<SecretIdentificationKey, D::Error> {
+    deserialize_scalar_from_hex_string(deserializer).map(SecretIdentificationKey::from)
+}
+
+/// The encodings in which `pk_root` may be exported
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PkRootEncoding {
+    /// The 65 byte uncompressed SEC1 encoding
+    Uncompressed,
+    /// The 33 byte compressed SEC1 encoding
+    Compressed,
+    /// The four scalar words committed to in the wallet, see
+    /// [`pk_root_to_scalars`]
+    Words,
+}
+
+impl FromStr for PkRootEncoding {
+    type Err = UtilsError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uncompressed" => Ok(PkRootEncoding::Uncompressed),
+            "compressed" => Ok(PkRootEncoding::Compressed),
+            "words" => Ok(PkRootEncoding::Words),
+            _ => Err(UtilsError::InvalidEncoding(format!(
+                "unknown pk_root encoding: {s}"
+            ))),
+        }
+    }
+}
+
+/// Encode `pk_root` as fixed-width, 0x-prefixed hex strings
+///
+/// The SEC1 encodings produce a single string, the scalar word encoding one
+/// string per word
+pub fn encode_pk_root(pk_root: &VerifyingKey, encoding: PkRootEncoding) -> Vec<String> {
+    let sec1_hex = |compress| {
+        let encoded = pk_root.as_affine().to_encoded_point(compress);
+        format!("0x{}", hex::encode(encoded.as_bytes()))
+    };
+
+    match encoding {
+        PkRootEncoding::Uncompressed => vec![sec1_hex(false)],
+        PkRootEncoding::Compressed => vec![sec1_hex(true)],
+        PkRootEncoding::Words => pk_root_to_scalars(pk_root)
+            .iter()
+            .map(scalar_to_fixed_hex_string)
+            .collect(),
+    }
+}
+
+/// Derive the ID of the wallet owned by `pk_root`
+///
+/// The ID is the UUIDv4 whose random bytes are the last 16 bytes of the
+/// SHA-256 hash of `pk_root`'s uncompressed SEC1 encoding, matching the ID
+/// the TS client has always registered wallets under
+pub fn derive_wallet_id(pk_root: &VerifyingKey) -> Uuid {
+    let encoded_key = pk_root.as_affine().to_encoded_point(false /* compress */);
+    let hash = compute_sha256_hash(encoded_key.as_bytes());
+
+    let mut random_bytes = [0u8; WALLET_ID_BYTES];
+    random_bytes.copy_from_slice(&hash[hash.len() - WALLET_ID_BYTES..]);
+    UuidBuilder::from_random_bytes(random_bytes).into_uuid()
+}
+
+/// Derive the Ethereum address of `pk_root`, the last 20 bytes of the keccak
+/// hash of its uncompressed SEC1 encoding, less the SEC1 tag byte
+pub fn address_from_pk_root(pk_root: &VerifyingKey) -> Address {
+    let encoded_key = pk_root.as_affine().to_encoded_point(false /* compress */);
+    let hash = keccak256(&encoded_key.as_bytes()[1..]);
+    Address::from_slice(&hash[hash.len() - ADDRESS_BYTES..])
+}
+
+/// Get the shares of the public keys in the key hierarchy of the given
+/// network computed from `sk_root`
+pub fn compute_key_hierarchy_shares(
+    sk_root: &SigningKey,
+    network: Network,
+) -> [ScalarField; NUM_KEY_HIERARCHY_SHARES] {
+    Keychain::from_sk_root(sk_root, network).pack()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{get_match_key, get_root_key};
+
+    /// A mock signature over the root key message
+    const MOCK_SIGNATURE: &[u8] = b"mock signature over the root key message";
+
+    #[test]
+    fn test_testnet_matches_legacy_derivation() {
+        let keys = derive_key_hierarchy(MOCK_SIGNATURE, Network::Testnet).unwrap();
+        let sk_root = derive_signing_key(MOCK_SIGNATURE).unwrap();
+        assert_eq!(keys.sk_root, sk_root);
+
+        let (sk_match, _) = get_match_key(sk_root.clone(), Network::Testnet);
+        assert_eq!(keys.sk_match.key(), sk_match.key());
+
+        // The seeds are the TS client's `sk_root + 1` and `sk_root + 2`
+        let sk_root_scalar = ScalarField::from_be_bytes_mod_order(&sk_root.to_bytes());
+        assert_eq!(keys.blinder_seed, sk_root_scalar + ScalarField::from(1u64));
+        assert_eq!(keys.share_seed, sk_root_scalar + ScalarField::from(2u64));
+        assert_eq!(
+            Network::Testnet.root_key_message(),
+            "Unlock your Renegade account.\nTestnet v0"
+        );
+    }
+
+    #[test]
+    fn test_networks_are_separated() {
+        let testnet = derive_key_hierarchy(MOCK_SIGNATURE, Network::Testnet).unwrap();
+        let mainnet = derive_key_hierarchy(MOCK_SIGNATURE, Network::Mainnet).unwrap();
+        assert_ne!(testnet.sk_match.key(), mainnet.sk_match.key());
+        assert_ne!(testnet.blinder_seed, mainnet.blinder_seed);
+        assert_ne!(testnet.share_seed, mainnet.share_seed);
+
+        // The seeds are separated from one another
+        assert_ne!(testnet.blinder_seed, testnet.share_seed);
+        assert_ne!(mainnet.blinder_seed, mainnet.share_seed);
+
+        // `sk_root` is bound to the network only by the message signed, so the
+        // same signature derives the same `sk_root` on both networks
+        assert_eq!(testnet.sk_root, mainnet.sk_root);
+        assert_ne!(
+            Network::Testnet.root_key_message(),
+            Network::Mainnet.root_key_message()
+        );
+    }
+
+    #[test]
+    fn test_key_hierarchy_serde() {
+        let sk_root = derive_signing_key(MOCK_SIGNATURE).unwrap();
+        let keys = KeyHierarchy::from_sk_root(&sk_root, Network::Testnet);
+        let serialized = serde_json::to_value(&keys).unwrap();
+
+        // Every key is encoded at a fixed width
+        let hex_len = |key: &serde_json::Value| key.as_str().unwrap().len();
+        assert_eq!(hex_len(&serialized["public_keys"]["pk_root"]), 2 + 2 * 65);
+        assert_eq!(hex_len(&serialized["public_keys"]["pk_match"]), 2 + 2 * 32);
+        assert_eq!(hex_len(&serialized["private_keys"]["sk_root"]), 2 + 2 * 32);
+        assert_eq!(hex_len(&serialized["private_keys"]["sk_match"]), 2 + 2 * 32);
+
+        let deserialized: KeyHierarchy = serde_json::from_value(serialized).unwrap();
+        assert_eq!(deserialized.public_keys, keys.public_keys);
+        assert_eq!(deserialized.private_keys.sk_root, keys.private_keys.sk_root);
+        assert_eq!(
+            deserialized.private_keys.sk_match.key(),
+            keys.private_keys.sk_match.key()
+        );
+    }
+
+    #[test]
+    fn test_encode_pk_root() {
+        let sk_root = derive_signing_key(MOCK_SIGNATURE).unwrap();
+        let pk_root = sk_root.verifying_key();
+        for encoding in [PkRootEncoding::Uncompressed, PkRootEncoding::Compressed] {
+            let encoded = encode_pk_root(pk_root, encoding);
+            let bytes = bytes_from_hex_string(&encoded[0]).unwrap();
+            assert_eq!(VerifyingKey::from_sec1_bytes(&bytes).unwrap(), *pk_root);
+        }
+
+        let words = encode_pk_root(pk_root, PkRootEncoding::Words);
+        assert_eq!(words.len(), NUM_ROOT_KEY_WORDS);
+        assert!(words.iter().all(|word| word.len() == 2 + 2 * 32));
+    }
+
+    #[test]
+    fn test_derive_wallet_id() {
+        // The expected ID is computed as the TS client's `generateId` does
+        let sk_root = derive_signing_key(MOCK_SIGNATURE).unwrap();
+        let wallet_id = derive_wallet_id(sk_root.verifying_key());
+        assert_eq!(
+            wallet_id.to_string(),
+            "4a5d7ff1-0a81-4248-ac07-e14ef26f27c1"
+        );
+        assert_eq!(wallet_id.get_version_num(), 4);
+    }
+
+    #[test]
+    fn test_address_from_pk_root() {
+        let (_, pk_root) = get_root_key("0x1").unwrap();
+        let expected =
+            Address::from_slice(&hex::decode("7e5f4552091a69125d5dfcb7b8c2659029395bdf").unwrap());
+        assert_eq!(address_from_pk_root(&pk_root), expected);
+    }
+
+    #[test]
+    fn test_derive_from_eth_key() {
+        use ethers::signers::LocalWallet;
+        use ethers::types::H256;
+        use ethers::utils::hash_message;
+
+        // The signature matches that of an Ethereum wallet's `personal_sign`
+        let (eth_key, _) =
+            get_root_key("0x4c0883a69102937d6231471b5dbb6204fe512961708279").unwrap();
+        let wallet = LocalWallet::from(eth_key.clone());
+        let message_hash = hash_message(Network::Testnet.root_key_message());
+        let expected_sig = wallet.sign_hash(H256(message_hash.0)).unwrap().to_vec();
+        let sig = sign_root_key_message(&eth_key, Network::Testnet).unwrap();
+        assert_eq!(*sig, expected_sig);
+
+        let keys = derive_key_hierarchy_from_eth_key(&eth_key, Network::Testnet).unwrap();
+        assert_eq!(keys.sk_root, derive_signing_key(&expected_sig).unwrap());
+    }
+
+    #[test]
+    fn test_indexed_accounts() {
+        let account_zero =
+            derive_account_key_hierarchy(MOCK_SIGNATURE, Network::Testnet, 0 /* index */).unwrap();
+        let legacy = derive_key_hierarchy(MOCK_SIGNATURE, Network::Testnet).unwrap();
+        assert_eq!(account_zero.sk_root, legacy.sk_root);
+
+        // Each account is recovered from its index alone, and is distinct from
+        // the others and from the same index on other networks
+        let account_two =
+            derive_account_key_hierarchy(MOCK_SIGNATURE, Network::Testnet, 2 /* index */).unwrap();
+        let mainnet_two =
+            derive_account_key_hierarchy(MOCK_SIGNATURE, Network::Mainnet, 2 /* index */).unwrap();
+        assert_ne!(account_two.sk_root, mainnet_two.sk_root);
+
+        let ids = derive_account_ids(MOCK_SIGNATURE, Network::Testnet, 3 /* count */).unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[0], derive_wallet_id(legacy.sk_root.verifying_key()));
+        assert_eq!(
+            ids[2],
+            derive_wallet_id(account_two.sk_root.verifying_key())
+        );
+        assert_ne!(ids[0], ids[1]);
+        assert_ne!(ids[1], ids[2]);
+
+        // Account zero is the legacy account, the same on every network
+        let mainnet_zero =
+            derive_account_key_hierarchy(MOCK_SIGNATURE, Network::Mainnet, 0 /* index */).unwrap();
+        assert_eq!(account_zero.sk_root, mainnet_zero.sk_root);
+
+        let res = derive_account_ids(MOCK_SIGNATURE, Network::Testnet, MAX_ACCOUNT_IDS + 1);
+        assert_eq!(res.unwrap_err().code(), "INVALID_ACCOUNT_COUNT");
+    }
+
+    #[test]
+    fn test_secrets_redacted() {
+        let keys = derive_key_hierarchy(MOCK_SIGNATURE, Network::Testnet).unwrap();
+        let sk_match_hex = keys.sk_match.serialize_to_hex();
+        let blinder_seed_hex = scalar_to_fixed_hex_string(&keys.blinder_seed);
+
+        let formatted = [
+            format!("{:?}", keys),
+            format!("{:?}", keys.sk_match),
+            format!("{}", keys.sk_match),
+            format!("{:?}", keys.sk_root),
+        ];
+        for s in formatted.iter() {
+            assert!(!s.contains(&sk_match_hex));
+            assert!(!s.contains(&blinder_seed_hex[2..]));
+            assert!(!s.contains(&hex::encode(keys.sk_root.to_bytes())));
+        }
+    }
+
+    #[test]
+    fn test_parse_network() {
+        assert_eq!("Mainnet".parse::<Network>().unwrap(), Network::Mainnet);
+        assert_eq!(
+            Network::Testnet.to_string().parse::<Network>().unwrap(),
+            Network::Testnet
+        );
+        assert_eq!(
+            "devnet".parse::<Network>().unwrap_err().code(),
+            "INVALID_NETWORK"
+        );
+    }
+}
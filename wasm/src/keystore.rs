@@ -0,0 +1,399 @@
+//! A password-encrypted keystore for `sk_root`, following Ethereum's keystore
+//! v3 format
+//!
+//! `sk_root` is the root of the key hierarchy, the rest of the hierarchy is
+//! re-derived from it on import. As `sk_root` is a secp256k1 key, keystores
+//! produced here may also be read by any tooling that reads v3 keystores
+
+use crate::errors::UtilsError;
+use aes::Aes128;
+use ctr::{
+    cipher::{KeyIvInit, StreamCipher},
+    Ctr128BE,
+};
+use ethers::utils::keccak256;
+use k256::ecdsa::SigningKey;
+use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+/// The version of the keystore format
+pub const KEYSTORE_VERSION: u8 = 3;
+/// The cipher used to encrypt the key
+const CIPHER_NAME: &str = "aes-128-ctr";
+/// The pseudorandom function used by pbkdf2
+const PBKDF2_PRF: &str = "hmac-sha256";
+/// The length of the key derived from the password
+const DERIVED_KEY_LEN: usize = 32;
+/// The length of the cipher key, the first half of the derived key
+const CIPHER_KEY_LEN: usize = 16;
+/// The length of the KDF salt
+const SALT_LEN: usize = 32;
+/// The length of the cipher IV
+const IV_LEN: usize = 16;
+
+/// The scrypt cost parameter used by geth's standard keystores
+const STANDARD_SCRYPT_N: u32 = 1 << 18;
+/// The scrypt block size parameter used by geth's standard keystores
+const STANDARD_SCRYPT_R: u32 = 8;
+/// The scrypt parallelization parameter used by geth's standard keystores
+const STANDARD_SCRYPT_P: u32 = 1;
+/// The pbkdf2 iteration count used by geth's standard keystores
+const STANDARD_PBKDF2_C: u32 = 262_144;
+
+// Scrypt allocates `128 * r * n` bytes and pbkdf2 runs `c` iterations, so
+// the parameters of an untrusted keystore are capped to keep a crafted
+// keystore from exhausting the module's memory or hanging it
+
+/// The maximum scrypt cost parameter accepted
+const MAX_SCRYPT_N: u32 = 1 << 20;
+/// The maximum scrypt block size parameter accepted
+const MAX_SCRYPT_R: u32 = 8;
+/// The maximum scrypt parallelization parameter accepted
+const MAX_SCRYPT_P: u32 = 16;
+/// The maximum pbkdf2 iteration count accepted
+const MAX_PBKDF2_C: u32 = 10_000_000;
+
+/// AES-128 in counter mode with a big endian counter
+type Aes128Ctr = Ctr128BE<Aes128>;
+
+/// An encrypted keystore holding `sk_root`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    /// The encrypted key and the parameters needed to decrypt it
+    pub crypto: KeystoreCrypto,
+    /// A random identifier of the keystore
+    pub id: Uuid,
+    /// The version of the keystore format
+    pub version: u8,
+}
+
+/// The encrypted key and its encryption parameters
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    /// The name of the cipher, always `aes-128-ctr`
+    pub cipher: String,
+    /// The parameters of the cipher
+    pub cipherparams: CipherParams,
+    /// The encrypted key
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+    pub ciphertext: Vec<u8>,
+    /// The key derivation function and its parameters
+    #[serde(flatten)]
+    pub kdf: KdfParams,
+    /// The MAC over the ciphertext, proving knowledge of the password
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+    pub mac: Vec<u8>,
+}
+
+/// The parameters of the cipher
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    /// The initial counter block
+    #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+    pub iv: Vec<u8>,
+}
+
+/// The key derivation function used to stretch the password, and its
+/// parameters
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum KdfParams {
+    /// The scrypt KDF
+    Scrypt {
+        /// The length of the derived key
+        dklen: u32,
+        /// The CPU/memory cost, a power of two
+        n: u32,
+        /// The parallelization parameter
+        p: u32,
+        /// The block size
+        r: u32,
+        /// The salt
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        salt: Vec<u8>,
+    },
+    /// The pbkdf2 KDF
+    Pbkdf2 {
+        /// The iteration count
+        c: u32,
+        /// The length of the derived key
+        dklen: u32,
+        /// The pseudorandom function, always `hmac-sha256`
+        prf: String,
+        /// The salt
+        #[serde(serialize_with = "serialize_hex", deserialize_with = "deserialize_hex")]
+        salt: Vec<u8>,
+    },
+}
+
+impl KdfParams {
+    /// The scrypt parameters of geth's standard keystores, with a fresh salt
+    pub fn standard_scrypt() -> Result<Self, UtilsError> {
+        Self::scrypt(STANDARD_SCRYPT_N, STANDARD_SCRYPT_R, STANDARD_SCRYPT_P)
+    }
+
+    /// Scrypt parameters with the given costs and a fresh salt
+    pub fn scrypt(n: u32, r: u32, p: u32) -> Result<Self, UtilsError> {
+        Ok(KdfParams::Scrypt {
+            dklen: DERIVED_KEY_LEN as u32,
+            n,
+            p,
+            r,
+            salt: random_bytes(SALT_LEN)?,
+        })
+    }
+
+    /// The pbkdf2 parameters of geth's standard keystores, with a fresh salt
+    pub fn standard_pbkdf2() -> Result<Self, UtilsError> {
+        Self::pbkdf2(STANDARD_PBKDF2_C)
+    }
+
+    /// Pbkdf2 parameters with the given iteration count and a fresh salt
+    pub fn pbkdf2(c: u32) -> Result<Self, UtilsError> {
+        Ok(KdfParams::Pbkdf2 {
+            c,
+            dklen: DERIVED_KEY_LEN as u32,
+            prf: PBKDF2_PRF.to_string(),
+            salt: random_bytes(SALT_LEN)?,
+        })
+    }
+
+    /// Stretch the password into the key that encrypts `sk_root` and
+    /// authenticates the ciphertext
+    ///
+    /// The derived key is wiped when dropped
+    fn derive_key(&self, password: &[u8]) -> Result<Zeroizing<Vec<u8>>, UtilsError> {
+        match self {
+            KdfParams::Scrypt {
+                dklen,
+                n,
+                p,
+                r,
+                salt,
+            } => {
+                check_dklen(*dklen)?;
+                if !n.is_power_of_two() || *n < 2 {
+                    return Err(UtilsError::Keystore(format!(
+                        "scrypt cost must be a power of two, got {n}"
+                    )));
+                }
+                if *n > MAX_SCRYPT_N || *r > MAX_SCRYPT_R || *p > MAX_SCRYPT_P {
+                    return Err(UtilsError::Keystore(format!(
+                        "scrypt params exceed n = {MAX_SCRYPT_N}, r = {MAX_SCRYPT_R}, \
+                         p = {MAX_SCRYPT_P}, got n = {n}, r = {r}, p = {p}"
+                    )));
+                }
+
+                let log_n = n.trailing_zeros() as u8;
+                let params = scrypt::Params::new(log_n, *r, *p)
+                    .map_err(|e| UtilsError::Keystore(format!("invalid scrypt params: {e}")))?;
+                let mut key = Zeroizing::new(vec![0u8; *dklen as usize]);
+                scrypt::scrypt(password, salt, &params, &mut key)
+                    .map_err(|e| UtilsError::Keystore(format!("scrypt failed: {e}")))?;
+                Ok(key)
+            }
+            KdfParams::Pbkdf2 {
+                c,
+                dklen,
+                prf,
+                salt,
+            } => {
+                check_dklen(*dklen)?;
+                if prf != PBKDF2_PRF {
+                    return Err(UtilsError::Keystore(format!("unsupported prf: {prf}")));
+                }
+                if *c == 0 || *c > MAX_PBKDF2_C {
+                    return Err(UtilsError::Keystore(format!(
+                        "pbkdf2 iterations must be between 1 and {MAX_PBKDF2_C}, got {c}"
+                    )));
+                }
+
+                let mut key = Zeroizing::new(vec![0u8; *dklen as usize]);
+                pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, *c, &mut key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+/// Encrypt `sk_root` under the given password, stretching it with the given
+/// KDF
+pub fn encrypt_sk_root(
+    sk_root: &SigningKey,
+    password: &str,
+    kdf: KdfParams,
+) -> Result<Keystore, UtilsError> {
+    let derived_key = kdf.derive_key(password.as_bytes())?;
+    let iv = random_bytes(IV_LEN)?;
+
+    // The buffer holds `sk_root` until encrypted in place
+    let sk_bytes = Zeroizing::new(sk_root.to_bytes());
+    let mut ciphertext = sk_bytes.to_vec();
+    apply_cipher(&derived_key, &iv, &mut ciphertext);
+    let mac = compute_mac(&derived_key, &ciphertext);
+
+    Ok(Keystore {
+        crypto: KeystoreCrypto {
+            cipher: CIPHER_NAME.to_string(),
+            cipherparams: CipherParams { iv },
+            ciphertext,
+            kdf,
+            mac,
+        },
+        id: Uuid::new_v4(),
+        version: KEYSTORE_VERSION,
+    })
+}
+
+/// Decrypt `sk_root` from a keystore with the given password
+pub fn decrypt_sk_root(keystore: &Keystore, password: &str) -> Result<SigningKey, UtilsError> {
+    let crypto = &keystore.crypto;
+    if keystore.version != KEYSTORE_VERSION {
+        return Err(UtilsError::Keystore(format!(
+            "unsupported keystore version: {}",
+            keystore.version
+        )));
+    }
+    if crypto.cipher != CIPHER_NAME {
+        return Err(UtilsError::Keystore(format!(
+            "unsupported cipher: {}",
+            crypto.cipher
+        )));
+    }
+    if crypto.cipherparams.iv.len() != IV_LEN {
+        return Err(UtilsError::Keystore(format!(
+            "iv must be {IV_LEN} bytes, got {}",
+            crypto.cipherparams.iv.len()
+        )));
+    }
+
+    let derived_key = crypto.kdf.derive_key(password.as_bytes())?;
+    let mac = compute_mac(&derived_key, &crypto.ciphertext);
+    if !bool::from(mac.as_slice().ct_eq(&crypto.mac)) {
+        return Err(UtilsError::InvalidPassword(
+            "keystore MAC does not match".to_string(),
+        ));
+    }
+
+    let mut plaintext = Zeroizing::new(crypto.ciphertext.clone());
+    apply_cipher(&derived_key, &crypto.cipherparams.iv, &mut plaintext);
+    SigningKey::from_slice(&plaintext)
+        .map_err(|e| UtilsError::KeyOutOfRange(format!("invalid sk_root in keystore: {e}")))
+}
+
+/// Encrypt or decrypt the buffer in place with the cipher key half of the
+/// derived key
+fn apply_cipher(derived_key: &[u8], iv: &[u8], buf: &mut [u8]) {
+    let mut cipher = Aes128Ctr::new(derived_key[..CIPHER_KEY_LEN].into(), iv.into());
+    cipher.apply_keystream(buf);
+}
+
+/// Compute the MAC of a ciphertext under the MAC key half of the derived key
+fn compute_mac(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    let preimage =
+        Zeroizing::new([&derived_key[CIPHER_KEY_LEN..DERIVED_KEY_LEN], ciphertext].concat());
+    keccak256(&*preimage).to_vec()
+}
+
+/// Check that a derived key length covers both the cipher and MAC keys
+fn check_dklen(dklen: u32) -> Result<(), UtilsError> {
+    if dklen as usize != DERIVED_KEY_LEN {
+        return Err(UtilsError::Keystore(format!(
+            "derived key must be {DERIVED_KEY_LEN} bytes, got {dklen}"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Sample the given number of random bytes
+fn random_bytes(len: usize) -> Result<Vec<u8>, UtilsError> {
+    let mut bytes = vec![0u8; len];
+    getrandom::getrandom(&mut bytes).map_err(|e| UtilsError::Randomness(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Serialize bytes as unprefixed hex, as in v3 keystores
+fn serialize_hex<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+/// Deserialize bytes from hex, with or without a `0x` prefix
+fn deserialize_hex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex_str = String::deserialize(deserializer)?;
+    let stripped = hex_str.strip_prefix("0x").unwrap_or(&hex_str);
+    hex::decode(stripped).map_err(D::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_helpers::mock_sk_root;
+
+    #[test]
+    fn test_scrypt_round_trip() {
+        let (sk_root, _) = mock_sk_root();
+        let kdf = KdfParams::scrypt(1 << 10, 8 /* r */, 1 /* p */).unwrap();
+        let keystore = encrypt_sk_root(&sk_root, "password", kdf).unwrap();
+
+        let serialized = serde_json::to_string(&keystore).unwrap();
+        let deserialized: Keystore = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, keystore);
+        assert_eq!(decrypt_sk_root(&deserialized, "password").unwrap(), sk_root);
+
+        let err = decrypt_sk_root(&deserialized, "wrong password").unwrap_err();
+        assert_eq!(err.code(), "INVALID_PASSWORD");
+    }
+
+    #[test]
+    fn test_kdf_params_capped() {
+        // Parameters that would exhaust the module's memory or hang it are
+        // rejected before the KDF runs
+        let (sk_root, _) = mock_sk_root();
+        let crafted = [
+            KdfParams::scrypt(1 << 31, 8 /* r */, 1 /* p */).unwrap(),
+            KdfParams::scrypt(1 << 10, 1 << 20 /* r */, 1 /* p */).unwrap(),
+            KdfParams::scrypt(1 << 10, 8 /* r */, 1 << 20 /* p */).unwrap(),
+            KdfParams::pbkdf2(u32::MAX).unwrap(),
+            KdfParams::pbkdf2(0).unwrap(),
+        ];
+        for kdf in crafted {
+            let err = encrypt_sk_root(&sk_root, "password", kdf).unwrap_err();
+            assert_eq!(err.code(), "KEYSTORE_ERROR");
+        }
+    }
+
+    /// The pbkdf2 test vector of the Web3 Secret Storage definition
+    #[test]
+    fn test_pbkdf2_test_vector() {
+        let keystore: Keystore = serde_json::from_str(
+            r#"{
+                "crypto": {
+                    "cipher": "aes-128-ctr",
+                    "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                    "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                    "kdf": "pbkdf2",
+                    "kdfparams": {
+                        "c": 262144,
+                        "dklen": 32,
+                        "prf": "hmac-sha256",
+                        "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                    },
+                    "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+                },
+                "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+                "version": 3
+            }"#,
+        )
+        .unwrap();
+
+        let sk_root = decrypt_sk_root(&keystore, "testpassword").unwrap();
+        assert_eq!(
+            hex::encode(sk_root.to_bytes()),
+            "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d"
+        );
+    }
+}
@@ -0,0 +1,277 @@
+//! A Poseidon2 Merkle tree following the conventions of the darkpool's state
+//! tree, and openings of its leaves
+//!
+//! Internal nodes are the two-to-one Poseidon2 hash of their children. Empty
+//! leaves hold [`struct@EMPTY_LEAF_VALUE`], so an empty subtree of height `i`
+//! has root `ZERO_VALUES[i]`
+
+use crate::{
+    errors::UtilsError,
+    helpers::{
+        _compute_poseidon_hash, deserialize_scalar_from_hex_string,
+        deserialize_scalars_from_hex_strings, serialize_scalar_to_hex_string,
+        serialize_scalars_to_hex_strings,
+    },
+    types::ScalarField,
+};
+use ark_ff::PrimeField;
+use ethers::utils::keccak256;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The height of the darkpool's state tree
+pub const MERKLE_HEIGHT: usize = 32;
+
+lazy_static! {
+    /// The value of an empty leaf, the keccak hash of the string "renegade"
+    /// reduced into the scalar field
+    pub static ref EMPTY_LEAF_VALUE: ScalarField =
+        ScalarField::from_be_bytes_mod_order(&keccak256(b"renegade"));
+
+    /// The roots of empty subtrees, indexed by the height of the subtree
+    pub static ref ZERO_VALUES: Vec<ScalarField> = {
+        let mut zeros = Vec::with_capacity(MERKLE_HEIGHT + 1);
+        zeros.push(*EMPTY_LEAF_VALUE);
+        for i in 0..MERKLE_HEIGHT {
+            zeros.push(hash_internal_nodes(zeros[i], zeros[i]));
+        }
+        zeros
+    };
+}
+
+/// Hash two sibling nodes into their parent
+pub fn hash_internal_nodes(left: ScalarField, right: ScalarField) -> ScalarField {
+    _compute_poseidon_hash(&[left, right])
+}
+
+/// An opening of a leaf in the Merkle tree, proving its inclusion under a root
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleOpening {
+    /// The value of the opened leaf
+    #[serde(
+        serialize_with = "serialize_scalar_to_hex_string",
+        deserialize_with = "deserialize_scalar_from_hex_string"
+    )]
+    pub value: ScalarField,
+    /// The index of the leaf in the tree
+    pub leaf_index: u64,
+    /// The siblings of the nodes on the path from the leaf to the root,
+    /// ordered from the leaf upwards
+    #[serde(
+        serialize_with = "serialize_scalars_to_hex_strings",
+        deserialize_with = "deserialize_scalars_from_hex_strings"
+    )]
+    pub path_siblings: Vec<ScalarField>,
+}
+
+impl MerkleOpening {
+    /// Compute the root that this opening opens the leaf under
+    pub fn compute_root(&self) -> Result<ScalarField, UtilsError> {
+        let height = self.path_siblings.len();
+        if height < u64::BITS as usize && self.leaf_index >> height != 0 {
+            return Err(UtilsError::InvalidMerkleProof(format!(
+                "leaf index {} does not fit in a tree of height {height}",
+                self.leaf_index
+            )));
+        }
+
+        let mut node = self.value;
+        for (i, sibling) in self.path_siblings.iter().enumerate() {
+            // The i-th bit of the index is set if the node is a right child
+            let is_right_child = i < u64::BITS as usize && (self.leaf_index >> i) & 1 == 1;
+            node = if is_right_child {
+                hash_internal_nodes(*sibling, node)
+            } else {
+                hash_internal_nodes(node, *sibling)
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Verify that this opening opens the leaf under the given root
+    pub fn verify(&self, root: ScalarField) -> Result<(), UtilsError> {
+        if self.compute_root()? != root {
+            return Err(UtilsError::InvalidMerkleProof(
+                "opening does not match the root".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A sparse Merkle tree, holding only the nodes above non-empty leaves
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    /// The height of the tree
+    height: usize,
+    /// The non-empty nodes of the tree, indexed by their height and then by
+    /// their index within that level
+    nodes: Vec<HashMap<u64, ScalarField>>,
+    /// The index of the next leaf to be inserted
+    next_index: u64,
+}
+
+impl Default for MerkleTree {
+    fn default() -> Self {
+        Self::new(MERKLE_HEIGHT).unwrap()
+    }
+}
+
+impl MerkleTree {
+    /// Create an empty tree of the given height
+    pub fn new(height: usize) -> Result<Self, UtilsError> {
+        if height == 0 || height > MERKLE_HEIGHT {
+            return Err(UtilsError::MerkleIndexOutOfRange(format!(
+                "tree height must be between 1 and {MERKLE_HEIGHT}, got {height}"
+            )));
+        }
+
+        Ok(Self {
+            height,
+            nodes: vec![HashMap::new(); height + 1],
+            next_index: 0,
+        })
+    }
+
+    /// The height of the tree
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The current root of the tree
+    pub fn root(&self) -> ScalarField {
+        self.get_node(self.height, 0 /* index */)
+    }
+
+    /// Insert a leaf at the next free index, returning the index
+    pub fn insert(&mut self, value: ScalarField) -> Result<u64, UtilsError> {
+        let index = self.next_index;
+        self.set_leaf(index, value)?;
+        Ok(index)
+    }
+
+    /// Set the leaf at the given index, recomputing the path to the root
+    pub fn set_leaf(&mut self, index: u64, value: ScalarField) -> Result<(), UtilsError> {
+        self.check_index(index)?;
+        self.next_index = self.next_index.max(index + 1);
+
+        let mut node_index = index;
+        let mut node = value;
+        self.nodes[0].insert(node_index, node);
+        for height in 0..self.height {
+            let sibling = self.get_node(height, node_index ^ 1);
+            node = if node_index & 1 == 1 {
+                hash_internal_nodes(sibling, node)
+            } else {
+                hash_internal_nodes(node, sibling)
+            };
+
+            node_index >>= 1;
+            self.nodes[height + 1].insert(node_index, node);
+        }
+
+        Ok(())
+    }
+
+    /// Get an opening of the leaf at the given index
+    pub fn get_opening(&self, index: u64) -> Result<MerkleOpening, UtilsError> {
+        self.check_index(index)?;
+        let path_siblings = (0..self.height)
+            .map(|height| self.get_node(height, (index >> height) ^ 1))
+            .collect();
+
+        Ok(MerkleOpening {
+            value: self.get_node(0 /* height */, index),
+            leaf_index: index,
+            path_siblings,
+        })
+    }
+
+    /// Get the node at the given height and index, defaulting to the root of
+    /// an empty subtree
+    fn get_node(&self, height: usize, index: u64) -> ScalarField {
+        self.nodes[height]
+            .get(&index)
+            .copied()
+            .unwrap_or(ZERO_VALUES[height])
+    }
+
+    /// Check that a leaf index fits in the tree
+    fn check_index(&self, index: u64) -> Result<(), UtilsError> {
+        if index >> self.height != 0 {
+            return Err(UtilsError::MerkleIndexOutOfRange(format!(
+                "leaf index {index} does not fit in a tree of height {}",
+                self.height
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_root() {
+        let tree = MerkleTree::new(4 /* height */).unwrap();
+        assert_eq!(tree.root(), ZERO_VALUES[4]);
+        assert_eq!(MerkleTree::default().root(), ZERO_VALUES[MERKLE_HEIGHT]);
+    }
+
+    #[test]
+    fn test_root_matches_naive() {
+        let leaves: Vec<ScalarField> = (1..=5u64).map(ScalarField::from).collect();
+        let mut tree = MerkleTree::new(3 /* height */).unwrap();
+        for leaf in leaves.iter() {
+            tree.insert(*leaf).unwrap();
+        }
+
+        // Hash up a fully populated layer of leaves
+        let mut layer = leaves.clone();
+        layer.resize(8, *EMPTY_LEAF_VALUE);
+        while layer.len() > 1 {
+            layer = layer
+                .chunks(2)
+                .map(|pair| hash_internal_nodes(pair[0], pair[1]))
+                .collect();
+        }
+        assert_eq!(tree.root(), layer[0]);
+    }
+
+    #[test]
+    fn test_opening() {
+        let mut tree = MerkleTree::new(5 /* height */).unwrap();
+        for i in 0..7u64 {
+            tree.insert(ScalarField::from(i + 10)).unwrap();
+        }
+
+        for index in [0, 3, 6, 20] {
+            let opening = tree.get_opening(index).unwrap();
+            assert!(opening.verify(tree.root()).is_ok());
+        }
+
+        // An opening does not verify for another leaf value
+        let mut opening = tree.get_opening(3).unwrap();
+        opening.value += ScalarField::from(1u8);
+        let err = opening.verify(tree.root()).unwrap_err();
+        assert_eq!(err.code(), "INVALID_MERKLE_PROOF");
+
+        let err = tree.get_opening(32).unwrap_err();
+        assert_eq!(err.code(), "MERKLE_INDEX_OUT_OF_RANGE");
+    }
+
+    #[test]
+    fn test_opening_serde() {
+        let mut tree = MerkleTree::new(3 /* height */).unwrap();
+        tree.insert(ScalarField::from(1u8)).unwrap();
+        let opening = tree.get_opening(0).unwrap();
+        let serialized = serde_json::to_string(&opening).unwrap();
+        let deserialized: MerkleOpening = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, opening);
+    }
+}
@@ -0,0 +1,118 @@
+//! BIP-39 mnemonic backup of the root key
+//!
+//! The mnemonic encodes the 32 bytes of `sk_root` directly as BIP-39 entropy,
+//! so it is always 24 words long. The rest of the key hierarchy, including the
+//! wallet ID, is derived from `sk_root` and so is restored with it
+
+use crate::{
+    errors::UtilsError,
+    keys::{KeyHierarchy, Network},
+};
+use bip39::{Language, Mnemonic};
+use k256::ecdsa::SigningKey;
+use zeroize::Zeroizing;
+
+/// The number of words in a mnemonic encoding `sk_root`
+pub const MNEMONIC_WORD_COUNT: usize = 24;
+
+/// Encode `sk_root` as a BIP-39 mnemonic
+///
+/// The mnemonic is as secret as `sk_root`, so it is zeroized when dropped
+pub fn sk_root_to_mnemonic(sk_root: &SigningKey) -> Zeroizing<String> {
+    let entropy = Zeroizing::new(sk_root.to_bytes());
+    // 32 bytes is always a valid entropy length
+    let mnemonic = Zeroizing::new(
+        Mnemonic::from_entropy_in(Language::English, &entropy)
+            .expect("sk_root is a valid entropy length"),
+    );
+    Zeroizing::new(mnemonic.to_string())
+}
+
+/// Decode `sk_root` from a BIP-39 mnemonic, validating its checksum
+pub fn sk_root_from_mnemonic(mnemonic: &str) -> Result<SigningKey, UtilsError> {
+    let mnemonic = Zeroizing::new(
+        Mnemonic::parse_in(Language::English, mnemonic)
+            .map_err(|e| UtilsError::InvalidMnemonic(e.to_string()))?,
+    );
+    if mnemonic.word_count() != MNEMONIC_WORD_COUNT {
+        return Err(UtilsError::InvalidMnemonic(format!(
+            "expected {MNEMONIC_WORD_COUNT} words, got {}",
+            mnemonic.word_count()
+        )));
+    }
+
+    let entropy = Zeroizing::new(mnemonic.to_entropy());
+    SigningKey::from_slice(&entropy)
+        .map_err(|e| UtilsError::KeyOutOfRange(format!("invalid sk_root in mnemonic: {e}")))
+}
+
+/// Restore the key hierarchy of the given network from a BIP-39 mnemonic
+pub fn restore_key_hierarchy(mnemonic: &str, network: Network) -> Result<KeyHierarchy, UtilsError> {
+    let sk_root = sk_root_from_mnemonic(mnemonic)?;
+    Ok(KeyHierarchy::from_sk_root(&sk_root, network))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::{get_root_key, test_helpers::mock_sk_root},
+        keys::derive_wallet_id,
+    };
+
+    /// 256 bit test vectors of the BIP-39 specification, as (entropy, mnemonic)
+    const BIP39_VECTORS: [(&str, &str); 2] = [
+        (
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+            "legal winner thank year wave sausage worth useful legal winner thank year wave \
+             sausage worth useful legal winner thank year wave sausage worth title",
+        ),
+        (
+            "8080808080808080808080808080808080808080808080808080808080808080",
+            "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd \
+             amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+        ),
+    ];
+
+    #[test]
+    fn test_bip39_vectors() {
+        for (entropy, mnemonic) in BIP39_VECTORS {
+            let (sk_root, _) = get_root_key(entropy).unwrap();
+            assert_eq!(*sk_root_to_mnemonic(&sk_root), mnemonic);
+            assert_eq!(sk_root_from_mnemonic(mnemonic).unwrap(), sk_root);
+        }
+    }
+
+    #[test]
+    fn test_restore_key_hierarchy() {
+        let (sk_root, pk_root) = mock_sk_root();
+        let original = KeyHierarchy::from_sk_root(&sk_root, Network::Testnet);
+
+        let mnemonic = sk_root_to_mnemonic(&sk_root);
+        let restored = restore_key_hierarchy(&mnemonic, Network::Testnet).unwrap();
+        assert_eq!(restored.private_keys.sk_root, sk_root);
+        assert_eq!(
+            restored.private_keys.sk_match.key(),
+            original.private_keys.sk_match.key()
+        );
+        assert_eq!(restored.public_keys.pk_root, pk_root);
+        assert_eq!(
+            derive_wallet_id(&restored.public_keys.pk_root),
+            derive_wallet_id(&pk_root)
+        );
+    }
+
+    #[test]
+    fn test_invalid_mnemonic() {
+        // Swapping the last word breaks the checksum
+        let (_, mnemonic) = BIP39_VECTORS[0];
+        let tampered = mnemonic.replace("title", "legal");
+        let err = sk_root_from_mnemonic(&tampered).unwrap_err();
+        assert_eq!(err.code(), "INVALID_MNEMONIC");
+
+        // A valid 12 word mnemonic does not encode a root key
+        let short = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        let err = sk_root_from_mnemonic(short).unwrap_err();
+        assert_eq!(err.code(), "INVALID_MNEMONIC");
+    }
+}
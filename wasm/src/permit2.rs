@@ -0,0 +1,343 @@
+//! Permit2 authorizations of deposits into the darkpool
+//!
+//! A deposit is authorized by the depositor signing a Permit2
+//! `PermitWitnessTransferFrom` that permits the darkpool to pull the deposited
+//! amount of the mint. The permit's witness is the darkpool's
+//! `DepositWitness`, the `pk_root` of the wallet deposited into, which the
+//! darkpool checks against the wallet before pulling the tokens. The
+//! signature therefore cannot be replayed to deposit into another wallet,
+//! while the permitted token and amount and the spender bind the remaining
+//! terms of the deposit
+
+use crate::{
+    eip712::{address_to_hex_string, encode_address, encode_uint, typed_data_signing_hash},
+    errors::UtilsError,
+    keys::{pk_root_to_scalars, NUM_ROOT_KEY_WORDS},
+    types::ContractExternalTransfer,
+};
+use alloy_primitives::{Address, U256};
+use ark_ff::PrimeField;
+use ethers::utils::keccak256;
+use k256::ecdsa::VerifyingKey;
+use serde_json::{json, Value};
+
+/// The address Permit2 is deployed at on every chain, by its CREATE2 salt
+pub const PERMIT2_ADDRESS: [u8; 20] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x22, 0xd4, 0x73, 0x03, 0x0f, 0x11, 0x6d, 0xde, 0xe9, 0xf6, 0xb4,
+    0x3a, 0xc7, 0x8b, 0xa3,
+];
+
+/// The name of Permit2's EIP-712 signing domain
+const PERMIT2_DOMAIN_NAME: &str = "Permit2";
+/// The EIP-712 type of Permit2's signing domain, which has no version
+const PERMIT2_DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,uint256 chainId,address verifyingContract)";
+/// The EIP-712 type of the tokens permitted by a permit
+const TOKEN_PERMISSIONS_TYPE: &str = "TokenPermissions(address token,uint256 amount)";
+/// Permit2's stub of the `PermitWitnessTransferFrom` type, which Permit2
+/// completes with the witness type string passed by the spender
+const PERMIT_WITNESS_TRANSFER_FROM_TYPE_STUB: &str = concat!(
+    "PermitWitnessTransferFrom(TokenPermissions permitted,address spender,uint256 nonce,",
+    "uint256 deadline,"
+);
+/// The witness type string the darkpool passes to Permit2 for a deposit: the
+/// witness field, then the referenced types in alphabetical order
+const DEPOSIT_WITNESS_TYPE_STRING: &str = concat!(
+    "DepositWitness witness)DepositWitness(uint256[4] pkRoot)",
+    "TokenPermissions(address token,uint256 amount)"
+);
+/// The EIP-712 type of a deposit's witness
+const DEPOSIT_WITNESS_TYPE: &str = "DepositWitness(uint256[4] pkRoot)";
+/// The name of the deposit witness type
+const DEPOSIT_WITNESS_TYPE_NAME: &str = "DepositWitness";
+/// The name of the permit type
+const PERMIT_WITNESS_TRANSFER_FROM_TYPE_NAME: &str = "PermitWitnessTransferFrom";
+
+/// Permit2's EIP-712 signing domain
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Permit2Domain {
+    /// The ID of the chain Permit2 is deployed on
+    pub chain_id: u64,
+    /// The address of the Permit2 contract
+    pub permit2_address: Address,
+}
+
+impl Permit2Domain {
+    /// Create a domain for the Permit2 contract at the given address and chain
+    pub fn new(chain_id: u64, permit2_address: Address) -> Self {
+        Self {
+            chain_id,
+            permit2_address,
+        }
+    }
+
+    /// Create a domain for the canonical Permit2 deployment on the given chain
+    pub fn canonical(chain_id: u64) -> Self {
+        Self::new(chain_id, Address::from(PERMIT2_ADDRESS))
+    }
+
+    /// The domain separator, the hash of the domain struct
+    pub fn separator(&self) -> [u8; 32] {
+        keccak256(
+            [
+                keccak256(PERMIT2_DOMAIN_TYPE),
+                keccak256(PERMIT2_DOMAIN_NAME),
+                encode_uint(U256::from(self.chain_id)),
+                encode_address(&self.permit2_address),
+            ]
+            .concat(),
+        )
+    }
+}
+
+/// A Permit2 `PermitWitnessTransferFrom` authorizing a deposit into the
+/// darkpool
+pub struct DepositPermit {
+    /// The deposit the permit authorizes
+    ///
+    /// The permitted token and amount are the mint and amount of the deposit,
+    /// and its account is the permit's signer
+    pub deposit: ContractExternalTransfer,
+    /// The `pk_root` of the wallet deposited into, which is witnessed by the
+    /// permit
+    pub pk_root: VerifyingKey,
+    /// The address of the darkpool contract, which spends the permit
+    pub spender: Address,
+    /// The Permit2 nonce of the permit, unique among the depositor's permits
+    pub nonce: U256,
+    /// The timestamp after which the permit expires, in seconds
+    pub deadline: U256,
+}
+
+impl DepositPermit {
+    /// Create a permit authorizing the darkpool to execute the given deposit
+    /// into the wallet of `pk_root`
+    pub fn new(
+        deposit: ContractExternalTransfer,
+        pk_root: VerifyingKey,
+        darkpool_address: Address,
+        nonce: U256,
+        deadline: U256,
+    ) -> Result<Self, UtilsError> {
+        if deposit.is_withdrawal {
+            return Err(UtilsError::InvalidPermit(
+                "a permit can only authorize a deposit".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            deposit,
+            pk_root,
+            spender: darkpool_address,
+            nonce,
+            deadline,
+        })
+    }
+
+    /// Compute the EIP-712 struct hash of the permit
+    pub fn struct_hash(&self) -> [u8; 32] {
+        let token_permissions_hash = keccak256(
+            [
+                keccak256(TOKEN_PERMISSIONS_TYPE),
+                encode_address(&self.deposit.mint),
+                encode_uint(self.deposit.amount),
+            ]
+            .concat(),
+        );
+
+        keccak256(
+            [
+                keccak256(permit_type()),
+                token_permissions_hash,
+                encode_address(&self.spender),
+                encode_uint(self.nonce),
+                encode_uint(self.deadline),
+                self.witness_hash(),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Compute the EIP-712 struct hash of the permit's `DepositWitness`
+    pub fn witness_hash(&self) -> [u8; 32] {
+        // A fixed size array is encoded as the hash of its encoded elements
+        let pk_root_words: Vec<u8> = self
+            .pk_root_words()
+            .into_iter()
+            .flat_map(encode_uint)
+            .collect();
+        keccak256([keccak256(DEPOSIT_WITNESS_TYPE), keccak256(pk_root_words)].concat())
+    }
+
+    /// The words of `pk_root` as the darkpool encodes them in the witness
+    fn pk_root_words(&self) -> [U256; NUM_ROOT_KEY_WORDS] {
+        pk_root_to_scalars(&self.pk_root).map(|word| U256::from_limbs(word.into_bigint().0))
+    }
+
+    /// Compute the EIP-712 signing hash of the permit in the given domain
+    pub fn signing_hash(&self, domain: &Permit2Domain) -> [u8; 32] {
+        typed_data_signing_hash(&domain.separator(), &self.struct_hash())
+    }
+
+    /// Build the `eth_signTypedData_v4` request of the permit in the given
+    /// domain, for a wallet to display and sign
+    pub fn typed_data(&self, domain: &Permit2Domain) -> Value {
+        json!({
+            "types": {
+                "EIP712Domain": [
+                    { "name": "name", "type": "string" },
+                    { "name": "chainId", "type": "uint256" },
+                    { "name": "verifyingContract", "type": "address" },
+                ],
+                PERMIT_WITNESS_TRANSFER_FROM_TYPE_NAME: [
+                    { "name": "permitted", "type": "TokenPermissions" },
+                    { "name": "spender", "type": "address" },
+                    { "name": "nonce", "type": "uint256" },
+                    { "name": "deadline", "type": "uint256" },
+                    { "name": "witness", "type": DEPOSIT_WITNESS_TYPE_NAME },
+                ],
+                "TokenPermissions": [
+                    { "name": "token", "type": "address" },
+                    { "name": "amount", "type": "uint256" },
+                ],
+                DEPOSIT_WITNESS_TYPE_NAME: [
+                    { "name": "pkRoot", "type": "uint256[4]" },
+                ],
+            },
+            "primaryType": PERMIT_WITNESS_TRANSFER_FROM_TYPE_NAME,
+            "domain": {
+                "name": PERMIT2_DOMAIN_NAME,
+                "chainId": domain.chain_id,
+                "verifyingContract": address_to_hex_string(&domain.permit2_address),
+            },
+            "message": {
+                "permitted": {
+                    "token": address_to_hex_string(&self.deposit.mint),
+                    "amount": self.deposit.amount.to_string(),
+                },
+                "spender": address_to_hex_string(&self.spender),
+                "nonce": self.nonce.to_string(),
+                "deadline": self.deadline.to_string(),
+                "witness": {
+                    "pkRoot": self.pk_root_words().map(|word| word.to_string()),
+                },
+            },
+        })
+    }
+}
+
+/// The full EIP-712 type of the permit, including its referenced types, as
+/// Permit2 assembles it from the darkpool's witness type string
+fn permit_type() -> String {
+    format!("{PERMIT_WITNESS_TRANSFER_FROM_TYPE_STUB}{DEPOSIT_WITNESS_TYPE_STRING}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::get_root_key;
+    use ethers::types::transaction::eip712::{Eip712, TypedData};
+
+    /// Permit2's `_TOKEN_PERMISSIONS_TYPEHASH`
+    const TOKEN_PERMISSIONS_TYPEHASH: &str =
+        "618358ac3db8dc274f0cd8829da7e234bd48cd73c4a740aede1adec9846d06a1";
+    /// The `_TYPE_HASH` of Permit2's signing domain
+    const PERMIT2_DOMAIN_TYPEHASH: &str =
+        "8cad95687ba82c2ce50e74f7b754645e5117c3a5bec8151c0726d5857980a866";
+    /// The hash of the darkpool's `DepositWitness` type
+    const DEPOSIT_WITNESS_TYPEHASH: &str =
+        "b5a87a0b6090ee2d5e082dd052d6b6404789b14ff1890447e8ef7c57dedd6391";
+    /// The hash of the permit type Permit2 assembles from the darkpool's
+    /// witness type string
+    const PERMIT_WITNESS_TRANSFER_FROM_TYPEHASH: &str =
+        "d9fb51be8a7dc96383a1f7cd09c294c57ab93a9a6262c399a60a34a680ba9512";
+    /// The signing hash of the mock permit on the canonical Permit2 deployment
+    const MOCK_PERMIT_SIGNING_HASH: &str =
+        "a41bfdd93af37bf8f4f5176a18e822d0e264d3473d4f1302e7af24072668e206";
+
+    /// Build a permit for a mock deposit
+    fn mock_permit() -> DepositPermit {
+        let deposit = ContractExternalTransfer {
+            account_addr: Address::from([0x11; 20]),
+            mint: Address::from([0x22; 20]),
+            amount: U256::from(1_000_000u64),
+            is_withdrawal: false,
+        };
+        let (_, pk_root) = get_root_key("0x1").unwrap();
+
+        DepositPermit::new(
+            deposit,
+            pk_root,
+            Address::from([0xda; 20]),
+            U256::from(42u64),
+            U256::from(1_700_000_000u64),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_typehashes() {
+        let typehash = |ty: &str| hex::encode(keccak256(ty));
+        assert_eq!(typehash(TOKEN_PERMISSIONS_TYPE), TOKEN_PERMISSIONS_TYPEHASH);
+        assert_eq!(typehash(PERMIT2_DOMAIN_TYPE), PERMIT2_DOMAIN_TYPEHASH);
+        assert_eq!(typehash(DEPOSIT_WITNESS_TYPE), DEPOSIT_WITNESS_TYPEHASH);
+        assert_eq!(
+            typehash(&permit_type()),
+            PERMIT_WITNESS_TRANSFER_FROM_TYPEHASH
+        );
+
+        let permit = mock_permit();
+        let domain = Permit2Domain::canonical(421614 /* chain_id */);
+        assert_eq!(
+            hex::encode(permit.signing_hash(&domain)),
+            MOCK_PERMIT_SIGNING_HASH
+        );
+    }
+
+    #[test]
+    fn test_typed_data_matches_signing_hash() {
+        let permit = mock_permit();
+        let domain = Permit2Domain::canonical(421614 /* chain_id */);
+        let typed_data: TypedData = serde_json::from_value(permit.typed_data(&domain)).unwrap();
+        assert_eq!(
+            typed_data.encode_eip712().unwrap(),
+            permit.signing_hash(&domain)
+        );
+
+        // The witness is `pk_root` as the wallet's shares encode it
+        let words = pk_root_to_scalars(&permit.pk_root);
+        let witness = &typed_data.message["witness"]["pkRoot"];
+        for (word, encoded) in words.iter().zip(witness.as_array().unwrap()) {
+            let word: num_bigint::BigUint = (*word).into();
+            assert_eq!(encoded.as_str().unwrap(), word.to_string());
+        }
+    }
+
+    #[test]
+    fn test_permit_witnesses_wallet() {
+        let domain = Permit2Domain::canonical(421614 /* chain_id */);
+        let permit = mock_permit();
+
+        // The permit is bound to the wallet deposited into
+        let mut other_permit = mock_permit();
+        other_permit.pk_root = get_root_key("0x2").unwrap().1;
+        assert_ne!(
+            permit.signing_hash(&domain),
+            other_permit.signing_hash(&domain)
+        );
+
+        let withdrawal = ContractExternalTransfer {
+            is_withdrawal: true,
+            ..Default::default()
+        };
+        let (_, pk_root) = get_root_key("0x1").unwrap();
+        let res = DepositPermit::new(
+            withdrawal,
+            pk_root,
+            Address::default(),
+            U256::ZERO,
+            U256::ZERO,
+        );
+        assert_eq!(res.err().unwrap().code(), "INVALID_PERMIT");
+    }
+}
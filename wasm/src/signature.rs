@@ -1,213 +1,990 @@
-use crate::helpers::{
-    _compute_poseidon_hash, deserialize_external_transfer, deserialize_wallet, get_root_key,
-    to_contract_external_transfer,
-};
 use crate::{
     custom_serde::BytesSerializable,
-    types::{ContractExternalTransfer, Wallet},
+    eip712::{external_transfer_signing_hash, external_transfer_typed_data, Eip712Domain},
+    errors::UtilsError,
+    keys::address_from_pk_root,
+    permit2::{DepositPermit, Permit2Domain},
+    signature_encoding::RecoverableSignature,
+    signer::Signer,
+    types::{ContractExternalTransfer, HttpRequest, WalletShares},
 };
 use base64::engine::{general_purpose as b64_general_purpose, Engine};
 use ethers::{
-    core::k256::ecdsa::SigningKey,
-    types::{Bytes, Signature as EthersSignature, U256},
+    types::{Bytes, Signature as EthersSignature},
     utils::keccak256,
 };
-use eyre::Result;
-use k256::ecdsa::{signature::Signer, Signature};
+use k256::ecdsa::{
+    signature::{hazmat::PrehashVerifier, Verifier},
+    Signature, VerifyingKey,
+};
 use serde::Serialize;
-use wasm_bindgen::prelude::*;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 
-const SIG_VALIDITY_WINDOW_MS: u64 = 10_000; // 10 seconds
+/// The default window of time for which an HTTP auth signature is valid
+pub const SIG_VALIDITY_WINDOW_MS: u64 = 10_000; // 10 seconds
+/// The header carrying the signature of an HTTP request
+pub const RENEGADE_AUTH_HEADER_NAME: &str = "renegade-auth";
+/// The header carrying the expiration of an HTTP request's signature
+pub const RENEGADE_AUTH_EXPIRATION_HEADER_NAME: &str = "renegade-auth-expiration";
 
-/// Generates wallet update statement signature.
-///
-/// # Arguments
-///
-/// * `wallet_str` - Serialized wallet data.
-/// * `sk_root` - sk_root in hex.
-///
-/// # Returns
-///
-/// A `JsValue` containing the hex-encoded signature string.
-#[wasm_bindgen]
-pub fn generate_wallet_update_signature(wallet_str: &str, sk_root: &str) -> JsValue {
-    let wallet = deserialize_wallet(wallet_str);
-    let (signing_key, _) = get_root_key(sk_root);
-    let sig = gen_update_wallet_signature(wallet, &signing_key);
-    let sig_bytes = sig.to_vec();
-    JsValue::from_str(&hex::encode(sig_bytes))
-}
-
-pub fn gen_update_wallet_signature(wallet: Wallet, signing_key: &SigningKey) -> EthersSignature {
-    // Get total shares
-    let shares_commitment = _compute_poseidon_hash(
-        &[
-            vec![wallet.get_private_share_commitment()],
-            wallet.blinded_public_shares,
-        ]
-        .concat(),
-    )
-    .serialize_to_bytes();
+/// Signs the commitment to the shares of the given wallet, authorizing an
+/// update to the wallet
+pub fn gen_update_wallet_signature(
+    wallet: &WalletShares,
+    signer: &impl Signer,
+) -> Result<EthersSignature, UtilsError> {
+    sign_prehash_checked(signer, &wallet_update_prehash(wallet)).map(|sig| sig.to_ethers())
+}
 
-    // Sign commitment
-    hash_and_sign_message(signing_key, &shares_commitment)
+/// Computes the prehash signed by [`gen_update_wallet_signature`]
+pub fn wallet_update_prehash(wallet: &WalletShares) -> [u8; 32] {
+    keccak256(wallet_shares_commitment_bytes(wallet))
 }
 
-/// Generates external transfer signature for withdrawals.
-///
-/// # Arguments
-///
-/// * `external_transfer_str` - Serialized external transfer data.
-/// * `sk_root` - sk_root in hex.
-///
-/// # Returns
-///
-/// A `JsValue` containing the hex-encoded signature string.
-#[wasm_bindgen]
-pub fn generate_external_transfer_signature(external_transfer_str: &str, sk_root: &str) -> JsValue {
-    let external_transfer = deserialize_external_transfer(external_transfer_str);
-    // JsValue::from_str(&serde_json::to_string(&external_transfer).unwrap())
-    let (signing_key, _) = get_root_key(sk_root);
-    let contract_external_transfer = to_contract_external_transfer(&external_transfer).unwrap();
-    let sig = gen_external_transfer_signature(contract_external_transfer, &signing_key);
-    let sig_bytes = sig.to_vec();
-    JsValue::from_str(&hex::encode(sig_bytes))
+/// Verifies a signature generated by [`gen_update_wallet_signature`] under
+/// `pk_root`
+pub fn verify_wallet_update_signature(
+    wallet: &WalletShares,
+    signature: &EthersSignature,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    let shares_commitment = wallet_shares_commitment_bytes(wallet);
+    hash_and_verify_message(verifying_key, &shares_commitment, signature)
 }
 
+/// Signs the calldata serialization of an external transfer, authorizing a
+/// withdrawal
 pub fn gen_external_transfer_signature(
     external_transfer: ContractExternalTransfer,
-    signing_key: &SigningKey,
-) -> EthersSignature {
-    let transfer_bytes = serialize_to_calldata(&external_transfer).unwrap();
-    hash_and_sign_message(signing_key, &transfer_bytes)
+    signer: &impl Signer,
+) -> Result<EthersSignature, UtilsError> {
+    let prehash = external_transfer_prehash(&external_transfer)?;
+    sign_prehash_checked(signer, &prehash).map(|sig| sig.to_ethers())
+}
+
+/// Computes the prehash signed by [`gen_external_transfer_signature`]
+pub fn external_transfer_prehash(
+    external_transfer: &ContractExternalTransfer,
+) -> Result<[u8; 32], UtilsError> {
+    serialize_to_calldata(external_transfer).map(keccak256)
+}
+
+/// Verifies a signature generated by [`gen_external_transfer_signature`]
+/// under `pk_root`
+pub fn verify_external_transfer_signature(
+    external_transfer: &ContractExternalTransfer,
+    signature: &EthersSignature,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    let transfer_bytes = serialize_to_calldata(external_transfer)?;
+    hash_and_verify_message(verifying_key, &transfer_bytes, signature)
+}
+
+/// Signs the EIP-712 typed data of an external transfer with sk_root,
+/// authorizing a withdrawal in a form that wallets can display to the user
+///
+/// The signer is handed the typed data along with its signing hash, see
+/// [`Signer::sign_typed_data`]. No deployed contract verifies signatures in
+/// the `Renegade` domain yet: the darkpool still verifies withdrawals against
+/// [`gen_external_transfer_signature`], so this signature does not replace it
+pub fn gen_external_transfer_typed_signature(
+    external_transfer: &ContractExternalTransfer,
+    domain: &Eip712Domain,
+    signer: &impl Signer,
+) -> Result<EthersSignature, UtilsError> {
+    let typed_data = external_transfer_typed_data(external_transfer, domain);
+    let signing_hash = external_transfer_signing_hash(external_transfer, domain);
+    sign_typed_data_checked(signer, &typed_data, &signing_hash).map(|sig| sig.to_ethers())
+}
+
+/// Verifies a signature generated by [`gen_external_transfer_typed_signature`]
+/// under `pk_root`
+pub fn verify_external_transfer_typed_signature(
+    external_transfer: &ContractExternalTransfer,
+    domain: &Eip712Domain,
+    signature: &EthersSignature,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    let signing_hash = external_transfer_signing_hash(external_transfer, domain);
+    verify_prehash(verifying_key, &signing_hash, signature)
+}
+
+/// Signs a Permit2 permit authorizing a deposit, with the key of the account
+/// depositing
+pub fn gen_deposit_permit_signature(
+    permit: &DepositPermit,
+    domain: &Permit2Domain,
+    signer: &impl Signer,
+) -> Result<EthersSignature, UtilsError> {
+    let typed_data = permit.typed_data(domain);
+    sign_typed_data_checked(signer, &typed_data, &permit.signing_hash(domain))
+        .map(|sig| sig.to_ethers())
 }
 
-/// Generates authorization headers for HTTP requests to a relayer.
+/// Verifies a signature generated by [`gen_deposit_permit_signature`] as
+/// Permit2 does, by recovering its signer and comparing it to the account
+/// depositing
 ///
-/// # Arguments
+/// As in Permit2, the signature is either the 65 byte `r || s || v` with `v`
+/// in `{27, 28}` or the 64 byte compact encoding, and either form of `s` is
+/// accepted, as by `ecrecover`
+pub fn verify_deposit_permit_signature(
+    permit: &DepositPermit,
+    domain: &Permit2Domain,
+    signature: &[u8],
+) -> Result<(), UtilsError> {
+    if signature.len() == 65 && !(27..=28).contains(&signature[64]) {
+        return Err(UtilsError::InvalidSignature(format!(
+            "permit signature has invalid v: {}",
+            signature[64]
+        )));
+    }
+
+    let sig = RecoverableSignature::from_bytes_malleable(signature)?;
+    let signer = VerifyingKey::recover_from_prehash(
+        &permit.signing_hash(domain),
+        sig.signature(),
+        sig.recovery_id(),
+    )
+    .map_err(|e| UtilsError::InvalidSignature(e.to_string()))?;
+
+    let signer_address = address_from_pk_root(&signer);
+    if signer_address != permit.deposit.account_addr {
+        return Err(UtilsError::InvalidSignature(format!(
+            "permit signed by {signer_address}, not the depositor"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Generates the authorization for an HTTP request to a relayer, valid for
+/// [`SIG_VALIDITY_WINDOW_MS`]
 ///
-/// * `message` - The message to be signed.
-/// * `timestamp` - The current timestamp.
-/// * `sk_root` - sk_root in hex.
+/// Returns the base64 encoded signature header and the expiration timestamp
+/// of the signature, in milliseconds
+pub fn gen_http_request_signature(
+    message: &[u8],
+    timestamp: u64,
+    signer: &impl Signer,
+) -> Result<(String, u64), UtilsError> {
+    gen_http_request_signature_with_window(message, timestamp, SIG_VALIDITY_WINDOW_MS, signer)
+}
+
+/// Generates the authorization for an HTTP request to a relayer, valid for
+/// `validity_window_ms` milliseconds after `timestamp`
 ///
-/// # Returns
+/// Returns the base64 encoded signature header and the expiration timestamp
+/// of the signature, in milliseconds
+pub fn gen_http_request_signature_with_window(
+    message: &[u8],
+    timestamp: u64,
+    validity_window_ms: u64,
+    signer: &impl Signer,
+) -> Result<(String, u64), UtilsError> {
+    let (payload, expiration) = http_request_payload(message, timestamp, validity_window_ms);
+    let sig = gen_message_signature(&payload, signer)?;
+    Ok((http_request_sig_header(&sig), expiration))
+}
+
+/// Builds the payload signed to authorize an HTTP request, valid for
+/// `validity_window_ms` milliseconds after `timestamp`
 ///
-/// * A vector of JavaScript values. The first element is the signature header,
-///   and the second element is the expiration time of the signature.
-#[wasm_bindgen]
-pub fn sign_http_request(message: &str, timestamp: u64, sk_root: &str) -> Vec<JsValue> {
-    let message_bytes = message.as_bytes();
-    let expiration = timestamp + SIG_VALIDITY_WINDOW_MS;
-    let payload = [message_bytes, &expiration.to_le_bytes()].concat();
-    let (signing_key, _) = get_root_key(sk_root);
-    let sig: Signature = signing_key.sign(&payload);
-    let sig_bytes = sig.to_bytes().to_vec();
-    let sig_header = b64_general_purpose::STANDARD_NO_PAD.encode(sig_bytes);
-    vec![
-        JsValue::from_str(&sig_header),
-        JsValue::from_str(&expiration.to_string()),
-    ]
-}
-
-/// Sign a message with sk_root
+/// Returns the payload, to be signed with [`gen_message_signature`], and the
+/// expiration timestamp of the signature, in milliseconds
+pub fn http_request_payload(
+    message: &[u8],
+    timestamp: u64,
+    validity_window_ms: u64,
+) -> (Vec<u8>, u64) {
+    let expiration = timestamp.saturating_add(validity_window_ms);
+    (expiring_payload(message, expiration), expiration)
+}
+
+/// Encodes a signature over an HTTP request's payload as the value of its
+/// [`RENEGADE_AUTH_HEADER_NAME`] header
+pub fn http_request_sig_header(signature: &RecoverableSignature) -> String {
+    b64_general_purpose::STANDARD_NO_PAD.encode(signature.signature().to_bytes())
+}
+
+/// Appends the expiration of a signature to the message it authorizes
+fn expiring_payload(message: &[u8], expiration: u64) -> Vec<u8> {
+    [message, &expiration.to_le_bytes()].concat()
+}
+
+/// Generates the authorization for an HTTP request to a relayer over its
+/// canonical form, see [`canonicalize_http_request`]
 ///
-/// # Arguments
+/// The signature and expiration are returned in the same format as
+/// [`gen_http_request_signature`], to be sent in the
+/// [`RENEGADE_AUTH_HEADER_NAME`] and [`RENEGADE_AUTH_EXPIRATION_HEADER_NAME`]
+/// headers
+pub fn gen_canonical_http_request_signature(
+    request: &HttpRequest,
+    timestamp: u64,
+    validity_window_ms: u64,
+    signer: &impl Signer,
+) -> Result<(String, u64), UtilsError> {
+    let canonical_request = canonicalize_http_request(request)?;
+    gen_http_request_signature_with_window(
+        &canonical_request,
+        timestamp,
+        validity_window_ms,
+        signer,
+    )
+}
+
+/// Verifies the authorization generated by
+/// [`gen_canonical_http_request_signature`] under `pk_root`
+pub fn verify_canonical_http_request_signature(
+    request: &HttpRequest,
+    expiration: u64,
+    sig_header: &str,
+    current_timestamp: u64,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    let canonical_request = canonicalize_http_request(request)?;
+    verify_http_request_signature(
+        &canonical_request,
+        expiration,
+        sig_header,
+        current_timestamp,
+        verifying_key,
+    )
+}
+
+/// Serializes the signed parts of an HTTP request into a canonical form
 ///
-/// * `message` - The message to be signed.
-/// * `sk_root` - sk_root in hex.
+/// The form is the newline separated list of: the uppercased method, the
+/// path, the query parameters sorted, one `name:value` line per header with
+/// names lowercased and sorted, then an empty line followed by the body. The
+/// auth headers themselves are never signed
+pub fn canonicalize_http_request(request: &HttpRequest) -> Result<Vec<u8>, UtilsError> {
+    let is_single_line = |s: &str| !s.contains(['\n', '\r']);
+    if !is_single_line(&request.method)
+        || !is_single_line(&request.path)
+        || !is_single_line(&request.query)
+    {
+        return Err(UtilsError::InvalidHttpRequest(
+            "method, path and query may not contain line breaks".to_string(),
+        ));
+    }
+
+    let mut query_params: Vec<&str> = request
+        .query
+        .split('&')
+        .filter(|param| !param.is_empty())
+        .collect();
+    query_params.sort_unstable();
+
+    let mut headers = Vec::with_capacity(request.headers.len());
+    for (name, value) in request.headers.iter() {
+        let name = name.trim().to_lowercase();
+        if name == RENEGADE_AUTH_HEADER_NAME || name == RENEGADE_AUTH_EXPIRATION_HEADER_NAME {
+            continue;
+        }
+
+        let value = value.trim();
+        if name.is_empty() || name.contains(':') || !is_single_line(&name) || !is_single_line(value)
+        {
+            return Err(UtilsError::InvalidHttpRequest(format!(
+                "invalid header: {name}"
+            )));
+        }
+        headers.push(format!("{name}:{value}"));
+    }
+    headers.sort_unstable();
+
+    let mut lines = vec![
+        request.method.to_uppercase(),
+        request.path.clone(),
+        query_params.join("&"),
+    ];
+    lines.extend(headers);
+
+    let mut canonical = lines.join("\n").into_bytes();
+    canonical.extend_from_slice(b"\n\n");
+    canonical.extend_from_slice(request.body.as_bytes());
+    Ok(canonical)
+}
+
+/// Verifies the authorization generated by [`gen_http_request_signature`]
+/// under `pk_root`
 ///
-/// # Returns
+/// The signature is rejected if `current_timestamp` is past its expiration
+pub fn verify_http_request_signature(
+    message: &[u8],
+    expiration: u64,
+    sig_header: &str,
+    current_timestamp: u64,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    if current_timestamp > expiration {
+        return Err(UtilsError::SignatureExpired(format!(
+            "signature expired at {expiration}, current time is {current_timestamp}"
+        )));
+    }
+
+    let sig_bytes = b64_general_purpose::STANDARD_NO_PAD
+        .decode(sig_header)
+        .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature header: {e}")))?;
+    let sig = Signature::from_slice(&sig_bytes)
+        .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature header: {e}")))?;
+    verify_message(&expiring_payload(message, expiration), &sig, verifying_key)
+}
+
+/// Signs the SHA-256 hash of a message with sk_root
 ///
-/// * A `JsValue` containing the hexadecimal string representation of the signature.
-#[wasm_bindgen]
-pub fn sign_message(message: &str, sk_root: &str) -> JsValue {
-    let message_bytes = message.as_bytes();
-    let (signing_key, _) = get_root_key(sk_root);
-    let sig: Signature = signing_key.sign(message_bytes);
-    let sig_hex = hex::encode(sig.to_bytes());
-    JsValue::from_str(&sig_hex)
+/// The signature is returned in its low-s form, the only form verifiers
+/// accept
+pub fn gen_message_signature(
+    message: &[u8],
+    signer: &impl Signer,
+) -> Result<RecoverableSignature, UtilsError> {
+    sign_prehash_checked(signer, &message_prehash(message))
+}
+
+/// Computes the prehash signed by [`gen_message_signature`], the SHA-256 hash
+/// of the message
+pub fn message_prehash(message: &[u8]) -> [u8; 32] {
+    Sha256::digest(message).into()
+}
+
+/// Verifies a signature generated by [`gen_message_signature`] under
+/// `pk_root`
+pub fn verify_message(
+    message: &[u8],
+    signature: &Signature,
+    verifying_key: &VerifyingKey,
+) -> Result<(), UtilsError> {
+    verifying_key
+        .verify(message, signature)
+        .map_err(|e| UtilsError::InvalidSignature(e.to_string()))
+}
+
+/// Computes the bytes signed to authorize an update to the given wallet, i.e.
+/// the commitment to its full sharing
+fn wallet_shares_commitment_bytes(wallet: &WalletShares) -> Vec<u8> {
+    wallet.get_share_commitment().serialize_to_bytes()
 }
 
 /// Serialize the given serializable type into a [`Bytes`] object
 /// that can be passed in as calldata
-pub fn serialize_to_calldata<T: Serialize>(t: &T) -> Result<Bytes> {
-    Ok(postcard::to_allocvec(t)?.into())
+pub fn serialize_to_calldata<T: Serialize>(t: &T) -> Result<Bytes, UtilsError> {
+    postcard::to_allocvec(t)
+        .map(Bytes::from)
+        .map_err(|e| UtilsError::Serde(format!("error serializing calldata: {e}")))
 }
 
-/// Hashes the given message and generates a signature over it using the signing
-/// key, as expected in ECDSA
-pub fn hash_and_sign_message(signing_key: &SigningKey, msg: &[u8]) -> EthersSignature {
+/// Hashes the given message and generates a signature over it using the
+/// signer, as expected in ECDSA
+pub fn hash_and_sign_message(
+    signer: &impl Signer,
+    msg: &[u8],
+) -> Result<EthersSignature, UtilsError> {
     let msg_hash = keccak256(msg);
-    let (sig, recovery_id) = signing_key.sign_prehash_recoverable(&msg_hash).unwrap();
-    let r: U256 = U256::from_big_endian(&sig.r().to_bytes());
-    let s: U256 = U256::from_big_endian(&sig.s().to_bytes());
-    EthersSignature {
-        r,
-        s,
-        v: recovery_id.to_byte() as u64,
+    sign_prehash_checked(signer, &msg_hash).map(|sig| sig.to_ethers())
+}
+
+/// Signs the given prehash, normalizing the signature with
+/// [`check_signature`]
+fn sign_prehash_checked(
+    signer: &impl Signer,
+    prehash: &[u8; 32],
+) -> Result<RecoverableSignature, UtilsError> {
+    let sig = signer.sign_prehash(prehash)?;
+    check_signature(prehash, &sig, signer.pk_root().as_ref())
+}
+
+/// Signs the EIP-712 signing hash of the given typed data, normalizing the
+/// signature with [`check_signature`]
+fn sign_typed_data_checked(
+    signer: &impl Signer,
+    typed_data: &Value,
+    prehash: &[u8; 32],
+) -> Result<RecoverableSignature, UtilsError> {
+    let sig = signer.sign_typed_data(typed_data, prehash)?;
+    check_signature(prehash, &sig, signer.pk_root().as_ref())
+}
+
+/// Normalizes a signer's signature over the given prehash to its low-s form,
+/// flipping the recovery ID to match, and checks that it recovers to
+/// `pk_root` if given
+///
+/// External signers may return either form of `s`, while the verifiers of
+/// these signatures only accept the low-s form
+pub fn check_signature(
+    prehash: &[u8; 32],
+    signature: &EthersSignature,
+    pk_root: Option<&VerifyingKey>,
+) -> Result<RecoverableSignature, UtilsError> {
+    let sig = RecoverableSignature::try_from(signature).map_err(|e| match e {
+        UtilsError::InvalidSignature(msg) => {
+            UtilsError::Signing(format!("signer returned invalid signature: {msg}"))
+        }
+        e => e,
+    })?;
+
+    if let Some(pk_root) = pk_root {
+        let signer = recover_signer(prehash, &sig.to_ethers())
+            .map_err(|e| UtilsError::Signing(format!("signer returned invalid signature: {e}")))?;
+        if signer != *pk_root {
+            return Err(UtilsError::Signing(
+                "signer's signature does not recover to pk_root".to_string(),
+            ));
+        }
     }
+
+    Ok(sig)
+}
+
+/// Hashes the given message and verifies a signature generated over it by
+/// [`hash_and_sign_message`]
+pub fn hash_and_verify_message(
+    verifying_key: &VerifyingKey,
+    msg: &[u8],
+    signature: &EthersSignature,
+) -> Result<(), UtilsError> {
+    let msg_hash = keccak256(msg);
+    verify_prehash(verifying_key, &msg_hash, signature)
+}
+
+/// The prefix of a message signed with EIP-191 `personal_sign`, followed by
+/// the decimal length of the message
+const PERSONAL_SIGN_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+
+/// Computes the hash an Ethereum wallet signs when asked to `personal_sign`
+/// the given message, as specified in EIP-191
+pub fn personal_sign_hash(message: &[u8]) -> [u8; 32] {
+    let prefix = format!("{PERSONAL_SIGN_PREFIX}{}", message.len());
+    keccak256([prefix.as_bytes(), message].concat())
 }
+
+/// Recovers the public key that generated a recoverable signature over the
+/// given prehash
+///
+/// `v` may be encoded either as the recovery ID or in the legacy Ethereum
+/// encoding, `{27, 28}`. As with `ecrecover`, a signature in its high-s form
+/// recovers the same key as its low-s form
+pub fn recover_signer(
+    prehash: &[u8; 32],
+    signature: &EthersSignature,
+) -> Result<VerifyingKey, UtilsError> {
+    let sig = RecoverableSignature::try_from(signature)?;
+    VerifyingKey::recover_from_prehash(prehash, sig.signature(), sig.recovery_id())
+        .map_err(|e| UtilsError::InvalidSignature(e.to_string()))
+}
+
+/// Verifies a signature over the given prehash
+fn verify_prehash(
+    verifying_key: &VerifyingKey,
+    prehash: &[u8; 32],
+    signature: &EthersSignature,
+) -> Result<(), UtilsError> {
+    let sig = signature_from_scalars(signature)
+        .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature scalars: {e}")))?;
+
+    verifying_key
+        .verify_prehash(prehash, &sig)
+        .map_err(|e| UtilsError::InvalidSignature(e.to_string()))
+}
+
+/// Builds the ECDSA signature with the `r` and `s` of the given signature
+pub(crate) fn signature_from_scalars(
+    signature: &EthersSignature,
+) -> Result<Signature, k256::ecdsa::Error> {
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    signature.r.to_big_endian(&mut r);
+    signature.s.to_big_endian(&mut s);
+    Signature::from_scalars(r, s)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use k256::ecdsa::{signature::Verifier, Signature};
+    use crate::{
+        helpers::{get_root_key, test_helpers::mock_sk_root},
+        signature_encoding::{signature_from_bytes, SignatureEncoding},
+        signer::ExternalSigner,
+        types::ScalarField,
+        wallet::SHARES_PER_WALLET,
+    };
+    use ethers::types::U256;
+    use k256::ecdsa::SigningKey;
+    use std::cell::RefCell;
+
+    /// The order of the secp256k1 group, in hex
+    const SECP256K1_ORDER: &str =
+        "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141";
+
+    /// Build a wallet with arbitrary shares
+    fn mock_shares() -> WalletShares {
+        WalletShares {
+            private_shares: (0..SHARES_PER_WALLET as u64)
+                .map(ScalarField::from)
+                .collect(),
+            blinded_public_shares: (100..100 + SHARES_PER_WALLET as u64)
+                .map(ScalarField::from)
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_verify_wallet_update_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let mut shares = mock_shares();
+        let sig = gen_update_wallet_signature(&shares, &signing_key).unwrap();
+        assert!(verify_wallet_update_signature(&shares, &sig, &verifying_key).is_ok());
+
+        // The signature does not authorize a different wallet
+        shares.blinded_public_shares[0] += ScalarField::from(1u8);
+        let err = verify_wallet_update_signature(&shares, &sig, &verifying_key).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+    }
+
+    #[test]
+    fn test_verify_external_transfer_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let (_, other_key) = get_root_key("0x1").unwrap();
+        let mock_transfer = || ContractExternalTransfer {
+            amount: alloy_primitives::U256::from(100u64),
+            is_withdrawal: true,
+            ..Default::default()
+        };
+
+        let sig = gen_external_transfer_signature(mock_transfer(), &signing_key).unwrap();
+        let transfer = mock_transfer();
+        assert!(verify_external_transfer_signature(&transfer, &sig, &verifying_key).is_ok());
+        assert!(verify_external_transfer_signature(&transfer, &sig, &other_key).is_err());
+    }
 
     #[test]
-    fn test_verify_hex_message() {
-        // Keypair
-        let hex_key = "05fb4b6c5af30b21e240d6c162d33599856bcb8c8489bc344da554ce96aa2a2a"; // Replace with an actual valid key hex string
-        let (signing_key, verifying_key) = get_root_key(hex_key);
-        let sk_root = hex::encode(signing_key.to_bytes());
-        let pk_root = hex::encode(verifying_key.to_encoded_point(false).as_bytes());
-        println!("SK ROOT: {}", sk_root);
-        println!("PK ROOT: {}", pk_root);
-
-        // // Message to sign
-        // let message =
-        //     r#"{"method":"subscribe","topic":"/v0/wallet/0ff3ccd1-c02a-4fd4-8db5-e6a4ff126522"}"#;
-        // let message_bytes = message.as_bytes();
-        // let expiration: u64 = 1709770523855;
-        // let payload1 = [message_bytes, &expiration.to_le_bytes()].concat();
-        // dbg!(payload1);
-
-        // Payload from relayer
-        let payload: Vec<u8> = [].to_vec();
-
-        // Reference Signature
-        // let b64_signature = "HCNtcq2EB+ufbV5FpHpnmYzVkwECf+vK83szezBU6+VxvYmQQBuquH7/jh+yi0bZe/rdGKEf7mW4SBxf2T8hQQ";
-
-        // Generated Signature
-        // let sig: Signature = signing_key.sign(&payload);
-        // let sig_b64 = b64_general_purpose::STANDARD_NO_PAD.encode(sig.to_bytes());
-        // println!("Base 64 Encoded Signature: {}", sig_b64);
-        let sig_b64 = "AsHgcWjOP3lylxuuzUBQtDIH3ojQZ1y80c3DZqqZMbkB0LOhLhkSe4P6F1D85hRUhq/nkYksPGbXcSUc74lA2A";
-        let sig_bytes = b64_general_purpose::STANDARD_NO_PAD
-            .decode(sig_b64)
+    fn test_prehashes_match_signatures() {
+        // The bindings sign these prehashes directly, so they must be those
+        // signed by the signing helpers
+        let (signing_key, verifying_key) = mock_sk_root();
+        let shares = mock_shares();
+        let sig = gen_update_wallet_signature(&shares, &signing_key).unwrap();
+        let signer = recover_signer(&wallet_update_prehash(&shares), &sig).unwrap();
+        assert_eq!(signer, verifying_key);
+
+        let transfer = ContractExternalTransfer::default();
+        let prehash = external_transfer_prehash(&transfer).unwrap();
+        let sig = gen_external_transfer_signature(transfer, &signing_key).unwrap();
+        assert_eq!(recover_signer(&prehash, &sig).unwrap(), verifying_key);
+
+        let (header, expiration) =
+            gen_http_request_signature_with_window(b"hello", 1, 2, &signing_key).unwrap();
+        let (payload, payload_expiration) = http_request_payload(b"hello", 1, 2);
+        assert_eq!(payload_expiration, expiration);
+        let sig = signing_key
+            .sign_prehash(&message_prehash(&payload))
             .unwrap();
+        let sig = RecoverableSignature::try_from(&sig).unwrap();
+        assert_eq!(http_request_sig_header(&sig), header);
+    }
+
+    #[test]
+    fn test_verify_external_transfer_typed_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let domain = Eip712Domain::new(421614 /* chain_id */, Default::default());
+        let mut transfer = ContractExternalTransfer {
+            amount: alloy_primitives::U256::from(100u64),
+            is_withdrawal: true,
+            ..Default::default()
+        };
+
+        let sig = gen_external_transfer_typed_signature(&transfer, &domain, &signing_key).unwrap();
+        let res =
+            verify_external_transfer_typed_signature(&transfer, &domain, &sig, &verifying_key);
+        assert!(res.is_ok());
+
+        // The signature does not authorize a different amount
+        transfer.amount = alloy_primitives::U256::from(101u64);
+        let err =
+            verify_external_transfer_typed_signature(&transfer, &domain, &sig, &verifying_key)
+                .unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+    }
+
+    /// A signer recording the typed data it is asked to sign
+    struct TypedDataSigner {
+        /// The key signing the prehash
+        signing_key: SigningKey,
+        /// The typed data last signed
+        typed_data: RefCell<Option<Value>>,
+    }
+
+    impl Signer for TypedDataSigner {
+        fn sign_prehash(&self, prehash: &[u8; 32]) -> Result<EthersSignature, UtilsError> {
+            self.signing_key.sign_prehash(prehash)
+        }
+
+        fn sign_typed_data(
+            &self,
+            typed_data: &Value,
+            prehash: &[u8; 32],
+        ) -> Result<EthersSignature, UtilsError> {
+            self.typed_data.replace(Some(typed_data.clone()));
+            self.sign_prehash(prehash)
+        }
+    }
+
+    #[test]
+    fn test_signer_receives_typed_data() {
+        let (signing_key, _) = mock_sk_root();
+        let domain = Eip712Domain::new(421614 /* chain_id */, Default::default());
+        let transfer = ContractExternalTransfer {
+            amount: alloy_primitives::U256::from(100u64),
+            is_withdrawal: true,
+            ..Default::default()
+        };
 
-        // Signature from relayer
-        // let sig_bytes: Vec<u8> = [
-        //     4, 234, 237, 146, 62, 73, 98, 66, 150, 15, 101, 144, 80, 181, 236, 212, 242, 88, 130,
-        //     201, 103, 86, 186, 194, 120, 106, 41, 39, 61, 115, 209, 41, 46, 91, 54, 127, 93, 55,
-        //     14, 47, 12, 245, 109, 111, 21, 167, 136, 147, 40, 208, 125, 231, 24, 82, 207, 87, 23,
-        //     197, 176, 215, 193, 104, 231, 196,
-        // ]
-        // .to_vec();
-
-        // Verify Message
-        // let sig = b64_general_purpose::STANDARD_NO_PAD
-        //     .decode(b64_signature)
-        //     .unwrap();
-        // let sig = Signature::from_slice(&sig).unwrap();
-        // let sig = Signature::from_bytes(&sig_bytes).unwrap();
-        let sig = Signature::from_slice(&sig_bytes).unwrap();
-        let expected: Signature = signing_key.sign(&payload);
-        println!(
-            "Expected: {:?}",
-            b64_general_purpose::STANDARD_NO_PAD.encode(expected.to_bytes())
+        let signer = TypedDataSigner {
+            signing_key: signing_key.clone(),
+            typed_data: RefCell::new(None),
+        };
+        let sig = gen_external_transfer_typed_signature(&transfer, &domain, &signer).unwrap();
+        let expected = gen_external_transfer_typed_signature(&transfer, &domain, &signing_key);
+        assert_eq!(sig, expected.unwrap());
+        assert_eq!(
+            signer.typed_data.take(),
+            Some(external_transfer_typed_data(&transfer, &domain))
         );
 
-        // Verify the signature
-        assert!(verifying_key.verify(&payload, &sig).is_ok())
+        // The calldata signature carries no typed data
+        gen_external_transfer_signature(transfer, &signer).unwrap();
+        assert_eq!(signer.typed_data.take(), None);
+    }
+
+    #[test]
+    fn test_verify_deposit_permit_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let (other_signing_key, _) = get_root_key("0x1").unwrap();
+        let domain = Permit2Domain::canonical(421614 /* chain_id */);
+        let deposit = ContractExternalTransfer {
+            account_addr: address_from_pk_root(&verifying_key),
+            amount: alloy_primitives::U256::from(100u64),
+            ..Default::default()
+        };
+        let permit = DepositPermit::new(
+            deposit,
+            verifying_key,
+            Default::default(),
+            alloy_primitives::U256::from(1u64),
+            alloy_primitives::U256::from(1_700_000_000u64),
+        )
+        .unwrap();
+
+        let sig = gen_deposit_permit_signature(&permit, &domain, &signing_key).unwrap();
+        let sig = RecoverableSignature::try_from(&sig).unwrap();
+        for encoding in [SignatureEncoding::RsvLegacy, SignatureEncoding::Compact] {
+            let res = verify_deposit_permit_signature(&permit, &domain, &sig.encode(encoding));
+            assert!(res.is_ok());
+        }
+
+        // As in Permit2, `v` must be in the legacy encoding...
+        let rsv = sig.encode(SignatureEncoding::Rsv);
+        let err = verify_deposit_permit_signature(&permit, &domain, &rsv).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+
+        // ...while the high-s form of the signature recovers the same signer
+        let high_s = Signature::from_scalars(sig.signature().r(), -*sig.signature().s()).unwrap();
+        let v = (sig.recovery_id().to_byte() ^ 1) + 27;
+        let high_s_bytes = [high_s.to_bytes().as_slice(), &[v]].concat();
+        assert!(verify_deposit_permit_signature(&permit, &domain, &high_s_bytes).is_ok());
+
+        // The signature must be the depositor's
+        let other_sig = gen_deposit_permit_signature(&permit, &domain, &other_signing_key).unwrap();
+        let other_sig = RecoverableSignature::try_from(&other_sig).unwrap();
+        let other_bytes = other_sig.encode(SignatureEncoding::RsvLegacy);
+        let err = verify_deposit_permit_signature(&permit, &domain, &other_bytes).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+
+        // The signature is bound to the chain
+        let other_domain = Permit2Domain::canonical(1 /* chain_id */);
+        let legacy = sig.encode(SignatureEncoding::RsvLegacy);
+        assert!(verify_deposit_permit_signature(&permit, &other_domain, &legacy).is_err());
+    }
+
+    #[test]
+    fn test_recover_personal_sign_signer() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let message = b"Unlock your Renegade account.";
+        let prehash = personal_sign_hash(message);
+        assert_eq!(prehash, ethers::utils::hash_message(message).0);
+
+        // Wallets return `v` in the legacy encoding
+        let mut sig = signing_key.sign_prehash(&prehash).unwrap();
+        sig.v += 27;
+        assert_eq!(recover_signer(&prehash, &sig).unwrap(), verifying_key);
+
+        // As with `ecrecover`, the high-s form of the signature recovers the
+        // same key
+        let high_s = EthersSignature {
+            r: sig.r,
+            s: U256::from_str_radix(SECP256K1_ORDER, 16).unwrap() - sig.s,
+            v: 27 + ((sig.v - 27) ^ 1),
+        };
+        assert_eq!(recover_signer(&prehash, &high_s).unwrap(), verifying_key);
+
+        // A signature over another message recovers another key
+        let other_hash = personal_sign_hash(b"Unlock your Renegade account?");
+        assert_ne!(recover_signer(&other_hash, &sig).unwrap(), verifying_key);
+
+        sig.v = 29;
+        let err = recover_signer(&prehash, &sig).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+    }
+
+    #[test]
+    fn test_verify_http_request_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let message = br#"{"method":"subscribe","topic":"/v0/wallet/0ff3ccd1"}"#;
+        let timestamp = 1709770513855;
+        let (header, expiration) =
+            gen_http_request_signature(message, timestamp, &signing_key).unwrap();
+        assert_eq!(expiration, timestamp + SIG_VALIDITY_WINDOW_MS);
+
+        let res = verify_http_request_signature(
+            message,
+            expiration,
+            &header,
+            timestamp + 1,
+            &verifying_key,
+        );
+        assert!(res.is_ok());
+
+        // The expiration is bound to the signature
+        let err = verify_http_request_signature(
+            message,
+            expiration + 1,
+            &header,
+            timestamp,
+            &verifying_key,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+
+        // The signature is rejected once expired
+        let err = verify_http_request_signature(
+            message,
+            expiration,
+            &header,
+            expiration + 1,
+            &verifying_key,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "SIGNATURE_EXPIRED");
+    }
+
+    #[test]
+    fn test_http_request_signature_window() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let timestamp = 1709770513855;
+        let (header, expiration) =
+            gen_http_request_signature_with_window(b"body", timestamp, 60_000, &signing_key)
+                .unwrap();
+        assert_eq!(expiration, timestamp + 60_000);
+
+        // Signatures with the default window remain compatible with the header format
+        let (default_header, default_expiration) =
+            gen_http_request_signature(b"body", timestamp, &signing_key).unwrap();
+        let (windowed_header, _) = gen_http_request_signature_with_window(
+            b"body",
+            timestamp,
+            SIG_VALIDITY_WINDOW_MS,
+            &signing_key,
+        )
+        .unwrap();
+        assert_eq!(default_header, windowed_header);
+
+        let res = verify_http_request_signature(
+            b"body",
+            expiration,
+            &header,
+            default_expiration + 1,
+            &verifying_key,
+        );
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_canonical_http_request_signature() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let request = HttpRequest {
+            method: "post".to_string(),
+            path: "/v0/wallet/0ff3ccd1/orders".to_string(),
+            query: "b=2&a=1".to_string(),
+            headers: [
+                ("Content-Type".to_string(), "application/json".to_string()),
+                (RENEGADE_AUTH_HEADER_NAME.to_string(), "ignored".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            body: r#"{"amount":"1"}"#.to_string(),
+        };
+        let canonical = canonicalize_http_request(&request).unwrap();
+        assert_eq!(
+            String::from_utf8(canonical).unwrap(),
+            "POST\n/v0/wallet/0ff3ccd1/orders\na=1&b=2\ncontent-type:application/json\n\n{\"amount\":\"1\"}"
+        );
+
+        let timestamp = 1709770513855;
+        let (header, expiration) =
+            gen_canonical_http_request_signature(&request, timestamp, 5_000, &signing_key).unwrap();
+        let res = verify_canonical_http_request_signature(
+            &request,
+            expiration,
+            &header,
+            timestamp,
+            &verifying_key,
+        );
+        assert!(res.is_ok());
+
+        // The signature does not transfer to another endpoint with the same body
+        let replayed = HttpRequest {
+            path: "/v0/wallet/0ff3ccd1/withdraw".to_string(),
+            ..request.clone()
+        };
+        let err = verify_canonical_http_request_signature(
+            &replayed,
+            expiration,
+            &header,
+            timestamp,
+            &verifying_key,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+    }
+
+    #[test]
+    fn test_verify_message() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let sig = gen_message_signature(b"hello", &signing_key).unwrap();
+        assert!(verify_message(b"hello", sig.signature(), &verifying_key).is_ok());
+        assert!(verify_message(b"goodbye", sig.signature(), &verifying_key).is_err());
+
+        // Signing the prehash matches signing the message directly
+        let direct_sig: Signature = k256::ecdsa::signature::Signer::sign(&signing_key, b"hello");
+        assert_eq!(*sig.signature(), direct_sig);
+    }
+
+    #[test]
+    fn test_verify_fixed_message_vectors() {
+        let (_, verifying_key) = mock_sk_root();
+        let decode = |sig_b64: &str| {
+            let sig_bytes = b64_general_purpose::STANDARD_NO_PAD
+                .decode(sig_b64)
+                .unwrap();
+            signature_from_bytes(&sig_bytes).unwrap()
+        };
+
+        // The RFC 6979 signature over the empty payload with the mock
+        // `sk_root`, which OpenSSL also verifies
+        let sig = decode(
+            "xxig9SoXFFuWEhOKZo35N1t9hmBTMFmjsyYUUcb4tyonP4T2cR1bdo/BMxxcF9rw8JnAARIr7z0dODscmxh5jQ",
+        );
+        assert!(verify_message(b"", &sig, &verifying_key).is_ok());
+        assert!(verify_message(b"hello", &sig, &verifying_key).is_err());
+
+        // The signature recorded from the relayer for the same key and payload.
+        // It does not verify under OpenSSL either, over the SHA-256 or keccak
+        // hash of the payload, so it was not produced with this key
+        let relayer_sig = decode(
+            "AsHgcWjOP3lylxuuzUBQtDIH3ojQZ1y80c3DZqqZMbkB0LOhLhkSe4P6F1D85hRUhq/nkYksPGbXcSUc74lA2A",
+        );
+        assert!(verify_message(b"", &relayer_sig, &verifying_key).is_err());
+    }
+
+    #[test]
+    fn test_verify_message_encodings() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let sig = gen_message_signature(b"hello", &signing_key).unwrap();
+
+        // Every encoding, and the plain `r || s`, parses back to the signature
+        let encodings = [
+            SignatureEncoding::Rsv,
+            SignatureEncoding::RsvLegacy,
+            SignatureEncoding::Compact,
+            SignatureEncoding::Der,
+        ];
+        let encoded = encodings
+            .map(|encoding| sig.encode(encoding))
+            .into_iter()
+            .chain([sig.signature().to_bytes().to_vec()]);
+        for bytes in encoded {
+            let parsed = signature_from_bytes(&bytes).unwrap();
+            assert_eq!(parsed, *sig.signature());
+            assert!(verify_message(b"hello", &parsed, &verifying_key).is_ok());
+        }
+
+        let err = signature_from_bytes(&[0u8; 10]).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+    }
+
+    #[test]
+    fn test_external_signer() {
+        let (signing_key, verifying_key) = mock_sk_root();
+        let signer = ExternalSigner::new(|prehash: &[u8; 32]| signing_key.sign_prehash(prehash));
+
+        let shares = mock_shares();
+        let sig = gen_update_wallet_signature(&shares, &signer).unwrap();
+        assert_eq!(
+            sig,
+            gen_update_wallet_signature(&shares, &signing_key).unwrap()
+        );
+        assert!(verify_wallet_update_signature(&shares, &sig, &verifying_key).is_ok());
+
+        let timestamp = 1709770513855;
+        let (header, expiration) = gen_http_request_signature(b"body", timestamp, &signer).unwrap();
+        let res =
+            verify_http_request_signature(b"body", expiration, &header, timestamp, &verifying_key);
+        assert!(res.is_ok());
+
+        // A high-s signature is normalized, flipping the recovery ID
+        let high_s_signer = ExternalSigner::with_pk_root(
+            |prehash: &[u8; 32]| {
+                let sig = signing_key.sign_prehash(prehash)?;
+                let n = U256::from_str_radix(SECP256K1_ORDER, 16).unwrap();
+                Ok(EthersSignature {
+                    r: sig.r,
+                    s: n - sig.s,
+                    v: sig.v ^ 1,
+                })
+            },
+            verifying_key,
+        );
+        assert_eq!(
+            gen_update_wallet_signature(&shares, &high_s_signer).unwrap(),
+            sig
+        );
+
+        // A signer holding a key other than pk_root is rejected
+        let (other_key, _) = get_root_key("0x1").unwrap();
+        let wrong_signer = ExternalSigner::with_pk_root(
+            |prehash: &[u8; 32]| other_key.sign_prehash(prehash),
+            verifying_key,
+        );
+        let err = gen_message_signature(b"hello", &wrong_signer).unwrap_err();
+        assert_eq!(err.code(), "SIGNING_ERROR");
+
+        // Errors of the external signer are propagated
+        let failing_signer = ExternalSigner::new(|_: &[u8; 32]| {
+            Err(UtilsError::Signing("signer unavailable".to_string()))
+        });
+        let err = gen_message_signature(b"hello", &failing_signer).unwrap_err();
+        assert_eq!(err.code(), "SIGNING_ERROR");
+    }
+
+    #[test]
+    fn test_invalid_root_key() {
+        let err = get_root_key("0xnothex").unwrap_err();
+        assert_eq!(err.code(), "INVALID_HEX");
+
+        let err = get_root_key("0x0").unwrap_err();
+        assert_eq!(err.code(), "KEY_OUT_OF_RANGE");
     }
 }
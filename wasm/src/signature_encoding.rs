@@ -0,0 +1,289 @@
+//! Encodings of the recoverable ECDSA signatures generated with `sk_root`
+//!
+//! Verifiers of these signatures disagree on their wire format: `ecrecover`
+//! based contracts take `r || s || v` with `v` either the recovery ID or in
+//! the legacy `{27, 28}` encoding, some contracts take the 64-byte compact
+//! form of EIP-2098, and non-EVM verifiers commonly take DER. A
+//! [`RecoverableSignature`] is rendered in the encoding the caller selects
+
+use crate::{
+    errors::UtilsError,
+    signature::signature_from_scalars,
+    signer::{recovery_id_from_v, LEGACY_V_OFFSET},
+};
+use ethers::types::{Signature as EthersSignature, U256};
+use k256::ecdsa::{RecoveryId, Signature};
+use std::str::FromStr;
+
+/// The length of a signature's `r` and `s` scalars, in bytes
+const SCALAR_BYTES: usize = 32;
+/// The mask of the bit of a compact signature's `s` holding the parity of
+/// the signature's `y` coordinate
+const COMPACT_Y_PARITY_MASK: u8 = 0x80;
+
+/// The encodings in which a recoverable signature may be rendered
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignatureEncoding {
+    /// The 65 byte `r || s || v`, with `v` the recovery ID in `{0, 1}`
+    Rsv,
+    /// The 65 byte `r || s || v`, with `v` in the legacy Ethereum encoding,
+    /// `{27, 28}`
+    RsvLegacy,
+    /// The 64 byte compact encoding of EIP-2098, `r || yParityAndS`
+    Compact,
+    /// The ASN.1 DER encoding of `r` and `s`, which omits the recovery ID
+    Der,
+}
+
+impl FromStr for SignatureEncoding {
+    type Err = UtilsError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rsv" => Ok(SignatureEncoding::Rsv),
+            "rsv-legacy" => Ok(SignatureEncoding::RsvLegacy),
+            "compact" => Ok(SignatureEncoding::Compact),
+            "der" => Ok(SignatureEncoding::Der),
+            _ => Err(UtilsError::InvalidEncoding(format!(
+                "unknown signature encoding: {s}"
+            ))),
+        }
+    }
+}
+
+/// An ECDSA signature together with the recovery ID of its signer's key
+///
+/// The signature is always held in its low-s form, which is the only form
+/// accepted by the verifiers of these signatures and which EIP-2098 requires
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RecoverableSignature {
+    /// The signature's `r` and `s`
+    signature: Signature,
+    /// The recovery ID of the signer's key
+    recovery_id: RecoveryId,
+}
+
+impl RecoverableSignature {
+    /// Create a recoverable signature, normalizing it to its low-s form
+    pub fn new(signature: Signature, recovery_id: RecoveryId) -> Self {
+        match signature.normalize_s() {
+            // Negating `s` negates the point the signature recovers to, so the
+            // parity of its `y` coordinate flips
+            Some(signature) => Self {
+                signature,
+                recovery_id: RecoveryId::new(!recovery_id.is_y_odd(), recovery_id.is_x_reduced()),
+            },
+            None => Self {
+                signature,
+                recovery_id,
+            },
+        }
+    }
+
+    /// Parse a signature in the 65 byte `r || s || v` encoding, with `v` in
+    /// either `{0, 1}` or `{27, 28}`, or in the 64 byte compact encoding
+    ///
+    /// Signatures in their high-s form are rejected rather than normalized,
+    /// so that a signature has exactly one accepted encoding
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, UtilsError> {
+        let (signature, recovery_id) = Self::parse(bytes)?;
+        if signature.normalize_s().is_some() {
+            return Err(UtilsError::InvalidSignature(
+                "signature is not in its low-s form".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            signature,
+            recovery_id,
+        })
+    }
+
+    /// Parse a signature as [`RecoverableSignature::from_bytes`] does, but
+    /// normalize a signature in its high-s form rather than rejecting it
+    ///
+    /// This matches `ecrecover`, which recovers the same key from either form
+    /// of a signature
+    pub fn from_bytes_malleable(bytes: &[u8]) -> Result<Self, UtilsError> {
+        let (signature, recovery_id) = Self::parse(bytes)?;
+        Ok(Self::new(signature, recovery_id))
+    }
+
+    /// Parse the signature and recovery ID of a 65 byte `r || s || v` or 64
+    /// byte compact signature, in whichever form of `s` it is given
+    fn parse(bytes: &[u8]) -> Result<(Signature, RecoveryId), UtilsError> {
+        let (r, s, recovery_id) = match bytes.len() {
+            65 => {
+                let v = bytes[2 * SCALAR_BYTES] as u64;
+                let recovery_id = recovery_id_from_v(v).ok_or_else(|| {
+                    UtilsError::InvalidSignature(format!("invalid recovery ID: {v}"))
+                })?;
+
+                let (r, s) = bytes[..2 * SCALAR_BYTES].split_at(SCALAR_BYTES);
+                (r, s.to_vec(), recovery_id)
+            }
+            64 => {
+                let (r, y_parity_and_s) = bytes.split_at(SCALAR_BYTES);
+                let mut s = y_parity_and_s.to_vec();
+                let recovery_id = (s[0] & COMPACT_Y_PARITY_MASK != 0) as u8;
+                s[0] &= !COMPACT_Y_PARITY_MASK;
+                (r, s, recovery_id)
+            }
+            len => {
+                return Err(UtilsError::InvalidSignature(format!(
+                    "invalid signature length: {len}"
+                )))
+            }
+        };
+
+        let signature = Signature::from_scalars(
+            *k256::FieldBytes::from_slice(r),
+            *k256::FieldBytes::from_slice(&s),
+        )
+        .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature scalars: {e}")))?;
+
+        let recovery_id = RecoveryId::from_byte(recovery_id).expect("recovery ID is 0 or 1");
+        Ok((signature, recovery_id))
+    }
+
+    /// The signature's `r` and `s`
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// The recovery ID of the signer's key
+    pub fn recovery_id(&self) -> RecoveryId {
+        self.recovery_id
+    }
+
+    /// Convert the signature to an ethers signature, with `v` the recovery ID
+    pub fn to_ethers(&self) -> EthersSignature {
+        let (r, s) = self.signature.split_bytes();
+        EthersSignature {
+            r: U256::from_big_endian(&r),
+            s: U256::from_big_endian(&s),
+            v: self.recovery_id.to_byte() as u64,
+        }
+    }
+
+    /// Encode the signature in the given encoding
+    pub fn encode(&self, encoding: SignatureEncoding) -> Vec<u8> {
+        let v = self.recovery_id.to_byte();
+        match encoding {
+            SignatureEncoding::Rsv => [self.signature.to_bytes().as_slice(), &[v]].concat(),
+            SignatureEncoding::RsvLegacy => {
+                let v = v + LEGACY_V_OFFSET as u8;
+                [self.signature.to_bytes().as_slice(), &[v]].concat()
+            }
+            SignatureEncoding::Compact => {
+                // The top bit of a low-s `s` is always clear, so it may hold
+                // the `y` parity
+                let mut bytes = self.signature.to_bytes().to_vec();
+                if self.recovery_id.is_y_odd() {
+                    bytes[SCALAR_BYTES] |= COMPACT_Y_PARITY_MASK;
+                }
+                bytes
+            }
+            SignatureEncoding::Der => self.signature.to_der().as_bytes().to_vec(),
+        }
+    }
+}
+
+/// Parse a signature in any of the [`SignatureEncoding`]s, for a verifier
+/// that does not need its recovery ID
+///
+/// A 64 byte signature is parsed as compact, which also covers a plain
+/// `r || s`, as the `y` parity bit of a low-s signature is clear
+pub fn signature_from_bytes(bytes: &[u8]) -> Result<Signature, UtilsError> {
+    match bytes.len() {
+        64 | 65 => RecoverableSignature::from_bytes(bytes).map(|sig| sig.signature),
+        _ => Signature::from_der(bytes)
+            .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature: {e}"))),
+    }
+}
+
+impl TryFrom<&EthersSignature> for RecoverableSignature {
+    type Error = UtilsError;
+    fn try_from(signature: &EthersSignature) -> Result<Self, Self::Error> {
+        let recovery_id = recovery_id_from_v(signature.v)
+            .and_then(RecoveryId::from_byte)
+            .ok_or_else(|| {
+                UtilsError::InvalidSignature(format!("invalid recovery ID: {}", signature.v))
+            })?;
+        let sig = signature_from_scalars(signature)
+            .map_err(|e| UtilsError::InvalidSignature(format!("invalid signature scalars: {e}")))?;
+
+        Ok(Self::new(sig, recovery_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::test_helpers::mock_sk_root, signature::recover_signer, signer::Signer};
+
+    /// The encodings that carry the recovery ID
+    const RECOVERABLE_ENCODINGS: [SignatureEncoding; 3] = [
+        SignatureEncoding::Rsv,
+        SignatureEncoding::RsvLegacy,
+        SignatureEncoding::Compact,
+    ];
+
+    #[test]
+    fn test_encodings_round_trip() {
+        let (signing_key, verifying_key) = mock_sk_root();
+
+        // Cover both parities of the recovery ID
+        for prehash in (0u8..8).map(|i| [i; 32]) {
+            let ethers_sig = signing_key.sign_prehash(&prehash).unwrap();
+            let sig = RecoverableSignature::try_from(&ethers_sig).unwrap();
+            assert_eq!(sig.to_ethers(), ethers_sig);
+
+            for encoding in RECOVERABLE_ENCODINGS {
+                let decoded = RecoverableSignature::from_bytes(&sig.encode(encoding)).unwrap();
+                assert_eq!(decoded, sig);
+                let signer = recover_signer(&prehash, &decoded.to_ethers()).unwrap();
+                assert_eq!(signer, verifying_key);
+            }
+
+            let der = sig.encode(SignatureEncoding::Der);
+            assert_eq!(Signature::from_der(&der).unwrap(), *sig.signature());
+        }
+    }
+
+    #[test]
+    fn test_compact_encoding() {
+        let (signing_key, _) = mock_sk_root();
+        let ethers_sig = signing_key.sign_prehash(&[1u8; 32]).unwrap();
+        let sig = RecoverableSignature::try_from(&ethers_sig).unwrap();
+
+        // The compact encoding is `r || s` with the `y` parity in the top bit
+        // of `s`
+        let rsv = sig.encode(SignatureEncoding::Rsv);
+        let mut compact = sig.encode(SignatureEncoding::Compact);
+        assert_eq!(compact[SCALAR_BYTES] >> 7, rsv[64]);
+        compact[SCALAR_BYTES] &= !COMPACT_Y_PARITY_MASK;
+        assert_eq!(compact, rsv[..64]);
+        assert_eq!(sig.encode(SignatureEncoding::RsvLegacy)[64], rsv[64] + 27);
+    }
+
+    #[test]
+    fn test_high_s_signature() {
+        let (signing_key, _) = mock_sk_root();
+        let ethers_sig = signing_key.sign_prehash(&[1u8; 32]).unwrap();
+        let sig = RecoverableSignature::try_from(&ethers_sig).unwrap();
+
+        // Negating `s` and flipping the parity gives the high-s form of the
+        // same signature, which is normalized on construction
+        let high_s = Signature::from_scalars(sig.signature().r(), -*sig.signature().s()).unwrap();
+        let flipped_id = RecoveryId::from_byte(sig.recovery_id().to_byte() ^ 1).unwrap();
+        assert_eq!(RecoverableSignature::new(high_s, flipped_id), sig);
+
+        // ...but rejected when parsed
+        let bytes = [high_s.to_bytes().as_slice(), &[flipped_id.to_byte()]].concat();
+        let err = RecoverableSignature::from_bytes(&bytes).unwrap_err();
+        assert_eq!(err.code(), "INVALID_SIGNATURE");
+
+        let err = "vrs".parse::<SignatureEncoding>().unwrap_err();
+        assert_eq!(err.code(), "INVALID_ENCODING");
+    }
+}
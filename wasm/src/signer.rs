@@ -0,0 +1,158 @@
+//! Signers producing the ECDSA signatures authorized by `sk_root`
+//!
+//! The signing helpers in [`crate::signature`] only ever hand a [`Signer`] the
+//! 32-byte prehash of the signed payload, along with its EIP-712 typed data
+//! where it has any, so the key may live in memory, as a [`SigningKey`], or
+//! behind an [`ExternalSigner`] such as a secure enclave or a remote signing
+//! service
+
+use crate::errors::UtilsError;
+use ethers::{
+    core::k256::ecdsa::{SigningKey, VerifyingKey},
+    types::{Signature as EthersSignature, U256},
+};
+use serde_json::Value;
+
+/// The offset added to the recovery ID in the legacy Ethereum `v` encoding
+pub(crate) const LEGACY_V_OFFSET: u64 = 27;
+
+/// A signer over secp256k1 prehashes
+pub trait Signer {
+    /// Sign the given prehash, returning the signature's `r`, `s` and the
+    /// recovery ID as `v`, in `{0, 1}`
+    fn sign_prehash(&self, prehash: &[u8; 32]) -> Result<EthersSignature, UtilsError>;
+
+    /// Sign the EIP-712 signing hash of the given typed data, as
+    /// [`Signer::sign_prehash`]
+    ///
+    /// The typed data is that of an `eth_signTypedData_v4` request, so that a
+    /// signer backed by a wallet may display it to the user. Signers that only
+    /// see the prehash need not implement this
+    fn sign_typed_data(
+        &self,
+        _typed_data: &Value,
+        prehash: &[u8; 32],
+    ) -> Result<EthersSignature, UtilsError> {
+        self.sign_prehash(prehash)
+    }
+
+    /// The key the signer's signatures must recover to, if known
+    ///
+    /// The signing helpers check every signature against it, so that a
+    /// signer holding the wrong key is caught before its signature is used
+    fn pk_root(&self) -> Option<VerifyingKey> {
+        None
+    }
+}
+
+impl Signer for SigningKey {
+    fn sign_prehash(&self, prehash: &[u8; 32]) -> Result<EthersSignature, UtilsError> {
+        let (sig, recovery_id) = self
+            .sign_prehash_recoverable(prehash)
+            .map_err(|e| UtilsError::Signing(e.to_string()))?;
+        let r: U256 = U256::from_big_endian(&sig.r().to_bytes());
+        let s: U256 = U256::from_big_endian(&sig.s().to_bytes());
+        Ok(EthersSignature {
+            r,
+            s,
+            v: recovery_id.to_byte() as u64,
+        })
+    }
+}
+
+/// A signer delegating to an external signing function, which receives the
+/// prehash and returns `r`, `s` and `v`
+///
+/// The function may encode `v` either as the recovery ID or in the legacy
+/// Ethereum encoding, `{27, 28}`, and may return either form of `s`
+pub struct ExternalSigner<F>
+where
+    F: Fn(&[u8; 32]) -> Result<EthersSignature, UtilsError>,
+{
+    /// The external signing function
+    sign_fn: F,
+    /// The key the function's signatures must recover to, if known
+    pk_root: Option<VerifyingKey>,
+}
+
+impl<F> ExternalSigner<F>
+where
+    F: Fn(&[u8; 32]) -> Result<EthersSignature, UtilsError>,
+{
+    /// Create a signer delegating to the given signing function
+    pub fn new(sign_fn: F) -> Self {
+        Self {
+            sign_fn,
+            pk_root: None,
+        }
+    }
+
+    /// Create a signer delegating to the given signing function, whose
+    /// signatures are checked to recover to `pk_root`
+    pub fn with_pk_root(sign_fn: F, pk_root: VerifyingKey) -> Self {
+        Self {
+            sign_fn,
+            pk_root: Some(pk_root),
+        }
+    }
+}
+
+impl<F> Signer for ExternalSigner<F>
+where
+    F: Fn(&[u8; 32]) -> Result<EthersSignature, UtilsError>,
+{
+    fn sign_prehash(&self, prehash: &[u8; 32]) -> Result<EthersSignature, UtilsError> {
+        let mut sig = (self.sign_fn)(prehash)?;
+        sig.v = recovery_id_from_v(sig.v).ok_or_else(|| {
+            UtilsError::Signing(format!(
+                "external signer returned invalid recovery ID: {}",
+                sig.v
+            ))
+        })? as u64;
+
+        Ok(sig)
+    }
+
+    fn pk_root(&self) -> Option<VerifyingKey> {
+        self.pk_root
+    }
+}
+
+/// Get the recovery ID of a signature from its `v`, encoded either as the
+/// recovery ID itself or in the legacy Ethereum encoding, `{27, 28}`
+pub(crate) fn recovery_id_from_v(v: u64) -> Option<u8> {
+    match v {
+        0 | 1 => Some(v as u8),
+        27 | 28 => Some((v - LEGACY_V_OFFSET) as u8),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::test_helpers::mock_sk_root;
+
+    #[test]
+    fn test_external_signer() {
+        let (signing_key, _) = mock_sk_root();
+        let prehash = [7u8; 32];
+        let expected = signing_key.sign_prehash(&prehash).unwrap();
+
+        // A signer returning the legacy `v` encoding is normalized
+        let legacy_signer = ExternalSigner::new(|prehash: &[u8; 32]| {
+            let mut sig = Signer::sign_prehash(&signing_key, prehash)?;
+            sig.v += LEGACY_V_OFFSET;
+            Ok(sig)
+        });
+        assert_eq!(legacy_signer.sign_prehash(&prehash).unwrap(), expected);
+
+        let bad_signer = ExternalSigner::new(|prehash: &[u8; 32]| {
+            let mut sig = Signer::sign_prehash(&signing_key, prehash)?;
+            sig.v = 2;
+            Ok(sig)
+        });
+        let err = bad_signer.sign_prehash(&prehash).unwrap_err();
+        assert_eq!(err.code(), "SIGNING_ERROR");
+    }
+}
@@ -1,13 +1,26 @@
 use crate::{
-    helpers::{_compute_poseidon_hash, biguint_to_scalar, deserialize_biguint_from_hex_string},
+    errors::UtilsError,
+    helpers::{
+        _compute_poseidon_hash, biguint_to_scalar, deserialize_biguint_from_hex_string,
+        deserialize_scalar_from_hex_string, serialize_scalar_to_hex_string,
+    },
     serde_def_types::{AddressDef, U256Def},
+    wallet::SHARES_PER_WALLET,
 };
 use alloy_primitives::{Address, U256};
 use ark_bn254::Fr;
-use ark_ec::{twisted_edwards::Projective, CurveGroup};
+use ark_ec::{
+    twisted_edwards::{Affine, Projective},
+    CurveGroup,
+};
 use num_bigint::BigUint;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_with::serde_as;
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display, Formatter, Result as FmtResult},
+};
+use zeroize::Zeroize;
 
 pub type ScalarField = Fr;
 
@@ -22,12 +35,23 @@ pub type EncryptionKey = BabyJubJubPoint;
 /// The config of the embedded curve
 pub type EmbeddedCurveConfig = ark_ed_on_bn254::EdwardsConfig;
 
+/// The scalar field of the embedded curve
+pub type EmbeddedScalarField = ark_ed_on_bn254::Fr;
+
 /// The affine representation of a point on the BabyJubJub curve
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BabyJubJubPoint {
     /// The x coordinate of the point
+    #[serde(
+        serialize_with = "serialize_scalar_to_hex_string",
+        deserialize_with = "deserialize_scalar_from_hex_string"
+    )]
     pub x: ScalarField,
     /// The y coordinate of the point
+    #[serde(
+        serialize_with = "serialize_scalar_to_hex_string",
+        deserialize_with = "deserialize_scalar_from_hex_string"
+    )]
     pub y: ScalarField,
 }
 impl From<Projective<EmbeddedCurveConfig>> for BabyJubJubPoint {
@@ -40,12 +64,28 @@ impl From<Projective<EmbeddedCurveConfig>> for BabyJubJubPoint {
     }
 }
 
+impl TryFrom<BabyJubJubPoint> for Projective<EmbeddedCurveConfig> {
+    type Error = UtilsError;
+    /// Converts the point into a curve group element, checking that it lies on
+    /// the curve and in the prime order subgroup
+    fn try_from(point: BabyJubJubPoint) -> Result<Self, Self::Error> {
+        let affine = Affine::<EmbeddedCurveConfig>::new_unchecked(point.x, point.y);
+        if !affine.is_on_curve() || !affine.is_in_correct_subgroup_assuming_on_curve() {
+            return Err(UtilsError::InvalidPoint(
+                "point is not in the Baby-JubJub prime order subgroup".to_string(),
+            ));
+        }
+        Ok(affine.into())
+    }
+}
+
 // ---------------------------
 // | IdentificationKey Types |
 // ---------------------------
 
 /// A public identification key is the image-under-hash of the secret
 /// identification key knowledge of which is proved in a circuit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct PublicIdentificationKey {
     pub key: ScalarField,
 }
@@ -65,8 +105,28 @@ impl From<ScalarField> for PublicIdentificationKey {
 
 /// A secret identification key is the hash preimage of the public
 /// identification key
+///
+/// The key is zeroized when dropped, and redacted when formatted
 pub struct SecretIdentificationKey {
-    pub key: ScalarField,
+    key: ScalarField,
+}
+
+impl Drop for SecretIdentificationKey {
+    fn drop(&mut self) {
+        self.key.zeroize();
+    }
+}
+
+impl Debug for SecretIdentificationKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("SecretIdentificationKey(<redacted>)")
+    }
+}
+
+impl Display for SecretIdentificationKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str("<redacted>")
+    }
 }
 
 impl From<ScalarField> for SecretIdentificationKey {
@@ -76,6 +136,14 @@ impl From<ScalarField> for SecretIdentificationKey {
 }
 
 impl SecretIdentificationKey {
+    /// The secret scalar
+    ///
+    /// The scalar is borrowed rather than copied out, so that the only copy
+    /// of it is wiped when the key is dropped
+    pub fn key(&self) -> &ScalarField {
+        &self.key
+    }
+
     /// Get the public key corresponding to this secret key
     pub fn get_public_key(&self) -> PublicIdentificationKey {
         let key = _compute_poseidon_hash(&[self.key]);
@@ -105,9 +173,19 @@ pub struct ApiWallet {
     pub private_shares: Vec<BigUint>,
 }
 
-impl TryFrom<ApiWallet> for Wallet {
-    type Error = String;
+impl TryFrom<ApiWallet> for WalletShares {
+    type Error = UtilsError;
     fn try_from(wallet: ApiWallet) -> Result<Self, Self::Error> {
+        if wallet.blinded_public_shares.len() != SHARES_PER_WALLET
+            || wallet.private_shares.len() != SHARES_PER_WALLET
+        {
+            return Err(UtilsError::InvalidShareCount(format!(
+                "expected {SHARES_PER_WALLET} shares, got {} public shares and {} private shares",
+                wallet.blinded_public_shares.len(),
+                wallet.private_shares.len()
+            )));
+        }
+
         // Deserialize the shares to scalar then re-structure into WalletSecretShare
         let blinded_public_shares: Vec<ScalarField> = wallet
             .blinded_public_shares
@@ -119,36 +197,138 @@ impl TryFrom<ApiWallet> for Wallet {
             .iter()
             .map(biguint_to_scalar)
             .collect();
-        Ok(Wallet {
+        Ok(WalletShares {
             blinded_public_shares,
             private_shares,
         })
     }
 }
 
-/// Represents a wallet managed by the local relayer
+impl From<&WalletShares> for ApiWallet {
+    fn from(shares: &WalletShares) -> Self {
+        ApiWallet {
+            blinded_public_shares: shares
+                .blinded_public_shares
+                .iter()
+                .map(|share| (*share).into())
+                .collect(),
+            private_shares: shares
+                .private_shares
+                .iter()
+                .map(|share| (*share).into())
+                .collect(),
+        }
+    }
+}
+
+/// The secret shares of a wallet managed by the local relayer
+///
+/// See [`crate::wallet::Wallet`] for the plaintext wallet these shares encode
 #[derive(Clone, Debug)]
-pub struct Wallet {
+pub struct WalletShares {
     /// The private secret shares of the wallet
     pub private_shares: Vec<ScalarField>,
     /// The public secret shares of the wallet
     pub blinded_public_shares: Vec<ScalarField>,
 }
 
-impl Wallet {
+impl WalletShares {
     /// Computes the commitment to the private shares of the wallet
     pub fn get_private_share_commitment(&self) -> ScalarField {
         compute_wallet_private_share_commitment(&self.private_shares)
     }
+
+    /// Computes the commitment to the full sharing of the wallet, the value
+    /// inserted into the state tree
+    pub fn get_share_commitment(&self) -> ScalarField {
+        compute_wallet_commitment_from_private(
+            &self.blinded_public_shares,
+            self.get_private_share_commitment(),
+        )
+    }
+
+    /// Recovers the wallet blinder from its private and public shares
+    ///
+    /// The public share of the blinder is not itself blinded, so the two
+    /// shares simply sum to the blinder
+    pub fn get_blinder(&self) -> Option<ScalarField> {
+        let private_share = self.private_shares.last()?;
+        let public_share = self.blinded_public_shares.last()?;
+        Some(*private_share + *public_share)
+    }
+
+    /// Computes the nullifier spent by the next update to the wallet
+    pub fn get_nullifier(&self) -> Result<ScalarField, UtilsError> {
+        let blinder = self.get_blinder().ok_or_else(|| {
+            UtilsError::InvalidShareCount("wallet has no blinder share".to_string())
+        })?;
+        Ok(compute_wallet_share_nullifier(
+            self.get_share_commitment(),
+            blinder,
+        ))
+    }
 }
 
 /// Compute a commitment to a single share of a wallet
-pub fn compute_wallet_private_share_commitment(private_share: &Vec<ScalarField>) -> ScalarField {
+pub fn compute_wallet_private_share_commitment(private_share: &[ScalarField]) -> ScalarField {
     _compute_poseidon_hash(private_share)
 }
 
+/// Compute the commitment to a full wallet sharing from the commitment to its
+/// private shares and its blinded public shares
+pub fn compute_wallet_commitment_from_private(
+    blinded_public_shares: &[ScalarField],
+    private_share_commitment: ScalarField,
+) -> ScalarField {
+    _compute_poseidon_hash(&[&[private_share_commitment], blinded_public_shares].concat())
+}
+
+/// Compute the nullifier of a wallet from its share commitment and blinder
+pub fn compute_wallet_share_nullifier(
+    share_commitment: ScalarField,
+    wallet_blinder: ScalarField,
+) -> ScalarField {
+    _compute_poseidon_hash(&[share_commitment, wallet_blinder])
+}
+
 /// The type used to track an amount
 pub type Amount = u128;
+
+// -------------------
+// | FixedPoint Type |
+// -------------------
+
+/// The number of bits of precision in the fractional part of a fixed point
+pub const FIXED_POINT_PRECISION_BITS: u32 = 32;
+
+/// A fixed point value, represented by a scalar shifted left by
+/// [`FIXED_POINT_PRECISION_BITS`]
+///
+/// Used for prices and fee rates in the wallet
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct FixedPoint {
+    /// The shifted representation of the value
+    pub repr: ScalarField,
+}
+
+impl Serialize for FixedPoint {
+    /// Serializes the representation as a decimal string, as the relayer does
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr: BigUint = self.repr.into();
+        serializer.serialize_str(&repr.to_string())
+    }
+}
+
+impl FixedPoint {
+    /// Construct a fixed point value from a float, rounding down to the
+    /// nearest representable value
+    pub fn from_f64_round_down(val: f64) -> Self {
+        let shifted = (val * 2f64.powi(FIXED_POINT_PRECISION_BITS as i32)).floor();
+        Self {
+            repr: ScalarField::from(shifted as u128),
+        }
+    }
+}
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ExternalTransfer {
     /// The address of the account contract to transfer to/from
@@ -187,3 +367,26 @@ pub enum ExternalTransferDirection {
     /// Withdraw an ERC20 from the darkpool to an external address
     Withdrawal,
 }
+
+// -------------------
+// | HTTP Auth Types |
+// -------------------
+
+/// The parts of an HTTP request to a relayer that are bound by a canonical
+/// request signature
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HttpRequest {
+    /// The request method, e.g. `POST`
+    pub method: String,
+    /// The path of the request, without the query string
+    pub path: String,
+    /// The query string of the request, without the leading `?`
+    #[serde(default)]
+    pub query: String,
+    /// The headers selected by the caller to be signed
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The body of the request
+    #[serde(default)]
+    pub body: String,
+}
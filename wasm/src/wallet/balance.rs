@@ -0,0 +1,47 @@
+//! The balance type held in a wallet
+
+use crate::{
+    errors::UtilsError,
+    helpers::{biguint_to_scalar, scalar_to_u128, serialize_biguint_to_hex_string},
+    types::{Amount, ScalarField},
+};
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use super::SHARES_PER_BALANCE;
+
+/// A balance of a single ERC20 token held in a wallet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Balance {
+    /// The mint (ERC20 address) of the token
+    #[serde(serialize_with = "serialize_biguint_to_hex_string")]
+    pub mint: BigUint,
+    /// The amount of the token held
+    pub amount: Amount,
+    /// The fees owed to the managing relayer
+    pub relayer_fee_balance: Amount,
+    /// The fees owed to the protocol
+    pub protocol_fee_balance: Amount,
+}
+
+impl Balance {
+    /// Pack the balance into its scalar representation
+    pub fn pack(&self) -> [ScalarField; SHARES_PER_BALANCE] {
+        [
+            biguint_to_scalar(&self.mint),
+            ScalarField::from(self.amount),
+            ScalarField::from(self.relayer_fee_balance),
+            ScalarField::from(self.protocol_fee_balance),
+        ]
+    }
+
+    /// Unpack a balance from its scalar representation
+    pub fn unpack(scalars: &[ScalarField; SHARES_PER_BALANCE]) -> Result<Self, UtilsError> {
+        Ok(Self {
+            mint: scalars[0].into(),
+            amount: scalar_to_u128(&scalars[1])?,
+            relayer_fee_balance: scalar_to_u128(&scalars[2])?,
+            protocol_fee_balance: scalar_to_u128(&scalars[3])?,
+        })
+    }
+}
@@ -0,0 +1,179 @@
+//! The keychain committed to in a wallet
+//!
+//! The keychain holds only public keys, so it may be used by watch-only and
+//! delegated clients that never hold `sk_root`
+
+use crate::{
+    errors::UtilsError,
+    helpers::{
+        deserialize_scalar_from_hex_string, get_match_key, pk_root_from_hex_string,
+        serialize_scalar_to_fixed_hex_string,
+    },
+    keys::{pk_root_from_scalars, pk_root_to_scalars, Network},
+    signature::{
+        verify_external_transfer_signature, verify_http_request_signature, verify_message,
+        verify_wallet_update_signature,
+    },
+    types::{
+        ContractExternalTransfer, PublicIdentificationKey, ScalarField, SecretIdentificationKey,
+        WalletShares,
+    },
+};
+use ethers::types::Signature as EthersSignature;
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use serde::{de::Error as DeserializeError, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::SHARES_PER_KEYCHAIN;
+
+/// The public keys of a wallet's key hierarchy, as committed to in the wallet
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keychain {
+    /// The public root key, authorizes updates to the wallet
+    #[serde(
+        serialize_with = "serialize_pk_root",
+        deserialize_with = "deserialize_pk_root"
+    )]
+    pub pk_root: VerifyingKey,
+    /// The public match key, authorizes matches on the wallet's orders
+    #[serde(
+        serialize_with = "serialize_pk_match",
+        deserialize_with = "deserialize_pk_match"
+    )]
+    pub pk_match: PublicIdentificationKey,
+}
+
+impl Keychain {
+    /// Build a view-only keychain from its public keys
+    pub fn new(pk_root: VerifyingKey, pk_match: PublicIdentificationKey) -> Self {
+        Self { pk_root, pk_match }
+    }
+
+    /// Build a keychain from `pk_root` and the secret match key, as held by a
+    /// client the match key has been delegated to
+    pub fn from_sk_match(pk_root: VerifyingKey, sk_match: &SecretIdentificationKey) -> Self {
+        Self::new(pk_root, sk_match.get_public_key())
+    }
+
+    /// Derive the keychain of the given network from the root key of the
+    /// hierarchy
+    pub fn from_sk_root(sk_root: &SigningKey, network: Network) -> Self {
+        let (_, pk_match) = get_match_key(sk_root.clone(), network);
+        Self {
+            pk_root: *sk_root.verifying_key(),
+            pk_match,
+        }
+    }
+
+    /// Pack the keychain into its scalar representation
+    pub fn pack(&self) -> [ScalarField; SHARES_PER_KEYCHAIN] {
+        let [x0, x1, y0, y1] = pk_root_to_scalars(&self.pk_root);
+        [x0, x1, y0, y1, self.pk_match.key]
+    }
+
+    /// Verify a signature authorizing an update to the given wallet
+    pub fn verify_wallet_update(
+        &self,
+        wallet: &WalletShares,
+        signature: &EthersSignature,
+    ) -> Result<(), UtilsError> {
+        verify_wallet_update_signature(wallet, signature, &self.pk_root)
+    }
+
+    /// Verify a signature authorizing the given external transfer
+    pub fn verify_external_transfer(
+        &self,
+        external_transfer: &ContractExternalTransfer,
+        signature: &EthersSignature,
+    ) -> Result<(), UtilsError> {
+        verify_external_transfer_signature(external_transfer, signature, &self.pk_root)
+    }
+
+    /// Verify the authorization of an HTTP request to a relayer
+    pub fn verify_http_request(
+        &self,
+        message: &[u8],
+        expiration: u64,
+        sig_header: &str,
+        current_timestamp: u64,
+    ) -> Result<(), UtilsError> {
+        verify_http_request_signature(
+            message,
+            expiration,
+            sig_header,
+            current_timestamp,
+            &self.pk_root,
+        )
+    }
+
+    /// Verify a message signed with `sk_root`
+    pub fn verify_message(&self, message: &[u8], signature: &Signature) -> Result<(), UtilsError> {
+        verify_message(message, signature, &self.pk_root)
+    }
+
+    /// Unpack a keychain from its scalar representation
+    pub fn unpack(scalars: &[ScalarField; SHARES_PER_KEYCHAIN]) -> Result<Self, UtilsError> {
+        let [x0, x1, y0, y1, pk_match] = *scalars;
+        Ok(Self {
+            pk_root: pk_root_from_scalars(&[x0, x1, y0, y1])?,
+            pk_match: PublicIdentificationKey::from(pk_match),
+        })
+    }
+}
+
+/// Serialize `pk_root` as the hex encoding of its uncompressed SEC1 form
+fn serialize_pk_root<S: Serializer>(
+    pk_root: &VerifyingKey,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let encoded = pk_root.to_encoded_point(false /* compress */);
+    serializer.serialize_str(&format!("0x{}", hex::encode(encoded.as_bytes())))
+}
+
+/// Deserialize `pk_root` from the hex encoding of its compressed or
+/// uncompressed SEC1 form
+fn deserialize_pk_root<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<VerifyingKey, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    pk_root_from_hex_string(&hex).map_err(D::Error::custom)
+}
+
+/// Serialize `pk_match` as the fixed-width hex encoding of its key
+fn serialize_pk_match<S: Serializer>(
+    pk_match: &PublicIdentificationKey,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serialize_scalar_to_fixed_hex_string(&pk_match.key, serializer)
+}
+
+/// Deserialize `pk_match` from the hex encoding of its key
+fn deserialize_pk_match<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<PublicIdentificationKey, D::Error> {
+    deserialize_scalar_from_hex_string(deserializer).map(PublicIdentificationKey::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{helpers::test_helpers::mock_sk_root, signature::gen_message_signature};
+
+    #[test]
+    fn test_view_only_keychain() {
+        let (sk_root, pk_root) = mock_sk_root();
+        let (sk_match, pk_match) = get_match_key(sk_root.clone(), Network::Testnet);
+        let full = Keychain::from_sk_root(&sk_root, Network::Testnet);
+        assert_ne!(Keychain::from_sk_root(&sk_root, Network::Mainnet), full);
+
+        // The keychain packs identically without sk_root
+        assert_eq!(Keychain::new(pk_root, pk_match).pack(), full.pack());
+        assert_eq!(Keychain::from_sk_match(pk_root, &sk_match), full);
+
+        let sig = gen_message_signature(b"hello", &sk_root).unwrap();
+        let view_only = Keychain::new(pk_root, pk_match);
+        assert!(view_only.verify_message(b"hello", sig.signature()).is_ok());
+        assert!(view_only
+            .verify_message(b"goodbye", sig.signature())
+            .is_err());
+    }
+}
@@ -0,0 +1,323 @@
+//! The plaintext wallet model and its packed scalar representation
+//!
+//! The packed layout matches the one expected by the relayer: balances, then
+//! orders, then the keychain, match fee, managing cluster and blinder
+
+use crate::{
+    errors::UtilsError,
+    helpers::serialize_scalar_to_hex_string,
+    keys::NUM_KEY_HIERARCHY_SHARES,
+    types::{EncryptionKey, FixedPoint, ScalarField, WalletShares},
+};
+use serde::Serialize;
+
+pub mod balance;
+pub mod keychain;
+pub mod order;
+pub mod shares;
+
+pub use balance::Balance;
+pub use keychain::Keychain;
+pub use order::{Order, OrderSide};
+
+/// The maximum number of balances that can be stored in a wallet
+pub const MAX_BALANCES: usize = 5;
+/// The maximum number of orders that can be stored in a wallet
+pub const MAX_ORDERS: usize = 5;
+
+/// The number of secret shares used to represent a balance
+pub const SHARES_PER_BALANCE: usize = 4;
+/// The number of secret shares used to represent an order
+pub const SHARES_PER_ORDER: usize = 5;
+/// The number of secret shares used to represent the keychain
+pub const SHARES_PER_KEYCHAIN: usize = NUM_KEY_HIERARCHY_SHARES;
+/// The number of secret shares used to represent the match fee
+pub const SHARES_PER_MATCH_FEE: usize = 1;
+/// The number of secret shares used to represent the managing cluster key
+pub const SHARES_PER_MANAGING_CLUSTER: usize = 2;
+/// The number of secret shares used to represent the blinder
+pub const SHARES_PER_BLINDER: usize = 1;
+
+/// The total number of secret shares used to represent a wallet
+pub const SHARES_PER_WALLET: usize = MAX_BALANCES * SHARES_PER_BALANCE
+    + MAX_ORDERS * SHARES_PER_ORDER
+    + SHARES_PER_KEYCHAIN
+    + SHARES_PER_MATCH_FEE
+    + SHARES_PER_MANAGING_CLUSTER
+    + SHARES_PER_BLINDER;
+
+/// The plaintext state of a wallet, holds all balances, orders, and
+/// randomness for a trader
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Wallet {
+    /// The balances held in the wallet, at most `MAX_BALANCES`
+    pub balances: Vec<Balance>,
+    /// The orders held in the wallet, at most `MAX_ORDERS`
+    pub orders: Vec<Order>,
+    /// The public keys authorizing updates and matches on the wallet
+    pub keychain: Keychain,
+    /// The fee rate the managing cluster takes on matches
+    pub match_fee: FixedPoint,
+    /// The key of the cluster managing the wallet
+    pub managing_cluster: EncryptionKey,
+    /// The blinder applied to the wallet's public shares
+    #[serde(serialize_with = "serialize_scalar_to_hex_string")]
+    pub blinder: ScalarField,
+}
+
+impl Wallet {
+    /// Pack the wallet into the scalar layout expected by the relayer,
+    /// padding the balances and orders with default values
+    pub fn pack(&self) -> Result<Vec<ScalarField>, UtilsError> {
+        if self.balances.len() > MAX_BALANCES {
+            return Err(UtilsError::InvalidWallet(format!(
+                "wallet has {} balances, at most {MAX_BALANCES} are allowed",
+                self.balances.len()
+            )));
+        }
+        if self.orders.len() > MAX_ORDERS {
+            return Err(UtilsError::InvalidWallet(format!(
+                "wallet has {} orders, at most {MAX_ORDERS} are allowed",
+                self.orders.len()
+            )));
+        }
+
+        let default_balance = Balance::default();
+        let balances = self
+            .balances
+            .iter()
+            .chain(std::iter::repeat(&default_balance))
+            .take(MAX_BALANCES)
+            .flat_map(Balance::pack);
+
+        let default_order = Order::default();
+        let orders = self
+            .orders
+            .iter()
+            .chain(std::iter::repeat(&default_order))
+            .take(MAX_ORDERS)
+            .flat_map(Order::pack);
+
+        let mut packed = Vec::with_capacity(SHARES_PER_WALLET);
+        packed.extend(balances);
+        packed.extend(orders);
+        packed.extend(self.keychain.pack());
+        packed.push(self.match_fee.repr);
+        packed.extend([self.managing_cluster.x, self.managing_cluster.y]);
+        packed.push(self.blinder);
+
+        Ok(packed)
+    }
+
+    /// Unpack a wallet from the scalar layout expected by the relayer, the
+    /// inverse of `pack`
+    ///
+    /// Trailing default balances and orders are treated as padding and
+    /// dropped
+    pub fn unpack(packed: &[ScalarField]) -> Result<Self, UtilsError> {
+        if packed.len() != SHARES_PER_WALLET {
+            return Err(UtilsError::InvalidShareCount(format!(
+                "expected {SHARES_PER_WALLET} scalars, got {}",
+                packed.len()
+            )));
+        }
+
+        let (balance_scalars, rest) = packed.split_at(MAX_BALANCES * SHARES_PER_BALANCE);
+        let (order_scalars, rest) = rest.split_at(MAX_ORDERS * SHARES_PER_ORDER);
+        let (keychain_scalars, rest) = rest.split_at(SHARES_PER_KEYCHAIN);
+        let (match_fee_scalars, rest) = rest.split_at(SHARES_PER_MATCH_FEE);
+        let (cluster_scalars, blinder_scalars) = rest.split_at(SHARES_PER_MANAGING_CLUSTER);
+
+        let mut balances = balance_scalars
+            .chunks_exact(SHARES_PER_BALANCE)
+            .map(|chunk| Balance::unpack(chunk.try_into().unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        while balances.last().is_some_and(|b| *b == Balance::default()) {
+            balances.pop();
+        }
+
+        let mut orders = order_scalars
+            .chunks_exact(SHARES_PER_ORDER)
+            .map(|chunk| Order::unpack(chunk.try_into().unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        while orders.last().is_some_and(|o| *o == Order::default()) {
+            orders.pop();
+        }
+
+        Ok(Self {
+            balances,
+            orders,
+            keychain: Keychain::unpack(keychain_scalars.try_into().unwrap())?,
+            match_fee: FixedPoint {
+                repr: match_fee_scalars[0],
+            },
+            managing_cluster: EncryptionKey {
+                x: cluster_scalars[0],
+                y: cluster_scalars[1],
+            },
+            blinder: blinder_scalars[0],
+        })
+    }
+
+    /// Reblind the wallet, consuming the next blinder and secret shares in the
+    /// streams seeded by its current private shares
+    ///
+    /// Returns the wallet with its new blinder, along with its new shares
+    pub fn reblind(
+        &self,
+        private_shares: &[ScalarField],
+    ) -> Result<(Self, WalletShares), UtilsError> {
+        let new_shares = shares::reblind(&self.pack()?, private_shares)?;
+        let new_blinder = new_shares.private_shares[SHARES_PER_WALLET - 1]
+            + new_shares.blinded_public_shares[SHARES_PER_WALLET - 1];
+
+        let wallet = Self {
+            blinder: new_blinder,
+            ..self.clone()
+        };
+        Ok((wallet, new_shares))
+    }
+
+    /// Reconstruct a wallet from its blinded public shares and private shares
+    pub fn from_shares(shares: &WalletShares) -> Result<Self, UtilsError> {
+        let packed = recombine_shares(&shares.private_shares, &shares.blinded_public_shares)?;
+        Self::unpack(&packed)
+    }
+}
+
+/// Remove the blinder from the public shares and recombine them with the
+/// private shares, recovering the packed wallet
+///
+/// The blinder's own shares are not blinded, so the blinder is recovered first
+/// from the last share of each vector
+pub fn recombine_shares(
+    private_shares: &[ScalarField],
+    blinded_public_shares: &[ScalarField],
+) -> Result<Vec<ScalarField>, UtilsError> {
+    if private_shares.len() != SHARES_PER_WALLET || blinded_public_shares.len() != SHARES_PER_WALLET
+    {
+        return Err(UtilsError::InvalidShareCount(format!(
+            "expected {SHARES_PER_WALLET} shares, got {} public shares and {} private shares",
+            blinded_public_shares.len(),
+            private_shares.len()
+        )));
+    }
+
+    let blinder =
+        private_shares[SHARES_PER_WALLET - 1] + blinded_public_shares[SHARES_PER_WALLET - 1];
+    let mut packed: Vec<ScalarField> = private_shares
+        .iter()
+        .zip(blinded_public_shares.iter())
+        .map(|(private, public)| *private + *public - blinder)
+        .collect();
+    packed[SHARES_PER_WALLET - 1] = blinder;
+
+    Ok(packed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        helpers::test_helpers::mock_sk_root, keys::Network, types::FIXED_POINT_PRECISION_BITS,
+    };
+    use num_bigint::BigUint;
+
+    /// Build a wallet with a single balance and order
+    fn mock_wallet() -> Wallet {
+        let (sk_root, _) = mock_sk_root();
+        Wallet {
+            balances: vec![Balance {
+                mint: BigUint::from(1u8),
+                amount: 100,
+                relayer_fee_balance: 2,
+                protocol_fee_balance: 3,
+            }],
+            orders: vec![Order {
+                quote_mint: BigUint::from(1u8),
+                base_mint: BigUint::from(2u8),
+                side: OrderSide::Sell,
+                amount: 50,
+                worst_case_price: FixedPoint::from_f64_round_down(1.5),
+            }],
+            keychain: Keychain::from_sk_root(&sk_root, Network::Testnet),
+            match_fee: FixedPoint::from_f64_round_down(0.0002),
+            managing_cluster: EncryptionKey {
+                x: ScalarField::from(7u8),
+                y: ScalarField::from(8u8),
+            },
+            blinder: ScalarField::from(9u8),
+        }
+    }
+
+    #[test]
+    fn test_pack_layout() {
+        let wallet = mock_wallet();
+        let packed = wallet.pack().unwrap();
+        assert_eq!(packed.len(), SHARES_PER_WALLET);
+
+        // The first balance, followed by padding
+        assert_eq!(packed[..SHARES_PER_BALANCE], wallet.balances[0].pack());
+        assert_eq!(packed[SHARES_PER_BALANCE], ScalarField::from(0u8));
+
+        // The first order, followed by padding
+        let orders_start = MAX_BALANCES * SHARES_PER_BALANCE;
+        let orders_end = orders_start + SHARES_PER_ORDER;
+        assert_eq!(packed[orders_start..orders_end], wallet.orders[0].pack());
+        assert_eq!(packed[orders_start + 2], ScalarField::from(1u8));
+        assert_eq!(
+            packed[orders_start + 4],
+            ScalarField::from(3u64 << (FIXED_POINT_PRECISION_BITS - 1))
+        );
+
+        // The trailing fields
+        let keychain_start = orders_start + MAX_ORDERS * SHARES_PER_ORDER;
+        let keychain_end = keychain_start + SHARES_PER_KEYCHAIN;
+        assert_eq!(packed[keychain_start..keychain_end], wallet.keychain.pack());
+        assert_eq!(packed[keychain_end], wallet.match_fee.repr);
+        assert_eq!(packed[keychain_end + 1], ScalarField::from(7u8));
+        assert_eq!(packed[keychain_end + 2], ScalarField::from(8u8));
+        assert_eq!(packed[SHARES_PER_WALLET - 1], wallet.blinder);
+    }
+
+    #[test]
+    fn test_unpack_inverts_pack() {
+        let wallet = mock_wallet();
+        let packed = wallet.pack().unwrap();
+        let unpacked = Wallet::unpack(&packed).unwrap();
+        assert_eq!(unpacked, wallet);
+    }
+
+    #[test]
+    fn test_from_shares() {
+        let wallet = mock_wallet();
+        let packed = wallet.pack().unwrap();
+
+        // Share the wallet with arbitrary private shares, blinding all but the
+        // blinder's public share
+        let private_shares: Vec<ScalarField> = (0..SHARES_PER_WALLET as u64)
+            .map(ScalarField::from)
+            .collect();
+        let mut blinded_public_shares: Vec<ScalarField> = packed
+            .iter()
+            .zip(private_shares.iter())
+            .map(|(value, private)| *value - *private + wallet.blinder)
+            .collect();
+        blinded_public_shares[SHARES_PER_WALLET - 1] =
+            wallet.blinder - private_shares[SHARES_PER_WALLET - 1];
+
+        let shares = WalletShares {
+            private_shares,
+            blinded_public_shares,
+        };
+        assert_eq!(Wallet::from_shares(&shares).unwrap(), wallet);
+    }
+
+    #[test]
+    fn test_pack_too_many_orders() {
+        let mut wallet = mock_wallet();
+        wallet.orders = vec![Order::default(); MAX_ORDERS + 1];
+        let err = wallet.pack().unwrap_err();
+        assert_eq!(err.code(), "INVALID_WALLET");
+    }
+}
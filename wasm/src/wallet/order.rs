@@ -0,0 +1,77 @@
+//! The order type held in a wallet
+
+use crate::{
+    errors::UtilsError,
+    helpers::{biguint_to_scalar, scalar_to_u128, serialize_biguint_to_hex_string},
+    types::{Amount, FixedPoint, ScalarField},
+};
+use num_bigint::BigUint;
+use serde::Serialize;
+
+use super::SHARES_PER_ORDER;
+
+/// The side of the market an order is on
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum OrderSide {
+    /// Buy the base token
+    #[default]
+    Buy = 0,
+    /// Sell the base token
+    Sell,
+}
+
+impl TryFrom<ScalarField> for OrderSide {
+    type Error = UtilsError;
+    fn try_from(value: ScalarField) -> Result<Self, Self::Error> {
+        if value == ScalarField::from(OrderSide::Buy as u64) {
+            Ok(OrderSide::Buy)
+        } else if value == ScalarField::from(OrderSide::Sell as u64) {
+            Ok(OrderSide::Sell)
+        } else {
+            Err(UtilsError::InvalidWallet(format!(
+                "invalid order side: {value}"
+            )))
+        }
+    }
+}
+
+/// An order held in a wallet
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Order {
+    /// The mint (ERC20 address) of the quote token
+    #[serde(serialize_with = "serialize_biguint_to_hex_string")]
+    pub quote_mint: BigUint,
+    /// The mint (ERC20 address) of the base token
+    #[serde(serialize_with = "serialize_biguint_to_hex_string")]
+    pub base_mint: BigUint,
+    /// The side of the order
+    pub side: OrderSide,
+    /// The amount of the base token to trade
+    pub amount: Amount,
+    /// The worst case price the order may be executed at
+    pub worst_case_price: FixedPoint,
+}
+
+impl Order {
+    /// Pack the order into its scalar representation
+    pub fn pack(&self) -> [ScalarField; SHARES_PER_ORDER] {
+        [
+            biguint_to_scalar(&self.quote_mint),
+            biguint_to_scalar(&self.base_mint),
+            ScalarField::from(self.side as u64),
+            ScalarField::from(self.amount),
+            self.worst_case_price.repr,
+        ]
+    }
+
+    /// Unpack an order from its scalar representation
+    pub fn unpack(scalars: &[ScalarField; SHARES_PER_ORDER]) -> Result<Self, UtilsError> {
+        Ok(Self {
+            quote_mint: scalars[0].into(),
+            base_mint: scalars[1].into(),
+            side: OrderSide::try_from(scalars[2])?,
+            amount: scalar_to_u128(&scalars[3])?,
+            worst_case_price: FixedPoint { repr: scalars[4] },
+        })
+    }
+}
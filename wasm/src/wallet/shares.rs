@@ -0,0 +1,179 @@
+//! Derivation of a wallet's secret shares from its seeds, and reblinding
+
+use crate::{
+    errors::UtilsError,
+    helpers::_compute_poseidon_hash,
+    types::{ScalarField, WalletShares},
+};
+
+use super::SHARES_PER_WALLET;
+
+/// Compute a chained Poseidon hash of the given length from the given seed
+pub fn evaluate_hash_chain(seed: ScalarField, length: usize) -> Vec<ScalarField> {
+    let mut seed = seed;
+    let mut res = Vec::with_capacity(length);
+    for _ in 0..length {
+        seed = _compute_poseidon_hash(&[seed]);
+        res.push(seed);
+    }
+
+    res
+}
+
+/// Sample the blinder and the private share of the blinder from the given seed
+///
+/// Returns the blinder followed by its private share
+pub fn derive_blinders(blinder_seed: ScalarField) -> (ScalarField, ScalarField) {
+    let chain = evaluate_hash_chain(blinder_seed, 2 /* length */);
+    (chain[0], chain[1])
+}
+
+/// Create a secret sharing of a packed wallet given the secret shares and
+/// blinders
+///
+/// The blinder itself is not blinded, its public share is simply the blinder
+/// less its private share
+pub fn create_wallet_shares_with_randomness(
+    packed_wallet: &[ScalarField],
+    blinder: ScalarField,
+    private_blinder_share: ScalarField,
+    secret_shares: &[ScalarField],
+) -> Result<WalletShares, UtilsError> {
+    if packed_wallet.len() != SHARES_PER_WALLET || secret_shares.len() != SHARES_PER_WALLET {
+        return Err(UtilsError::InvalidShareCount(format!(
+            "expected {SHARES_PER_WALLET} shares, got {} wallet scalars and {} secret shares",
+            packed_wallet.len(),
+            secret_shares.len()
+        )));
+    }
+
+    let mut private_shares = secret_shares.to_vec();
+    let mut blinded_public_shares: Vec<ScalarField> = packed_wallet
+        .iter()
+        .zip(secret_shares.iter())
+        .map(|(value, secret_share)| *value - *secret_share + blinder)
+        .collect();
+
+    private_shares[SHARES_PER_WALLET - 1] = private_blinder_share;
+    blinded_public_shares[SHARES_PER_WALLET - 1] = blinder - private_blinder_share;
+
+    Ok(WalletShares {
+        private_shares,
+        blinded_public_shares,
+    })
+}
+
+/// Derive the initial shares of a packed wallet from the blinder and share
+/// seeds
+pub fn derive_wallet_shares(
+    packed_wallet: &[ScalarField],
+    blinder_seed: ScalarField,
+    share_seed: ScalarField,
+) -> Result<WalletShares, UtilsError> {
+    let (blinder, private_blinder_share) = derive_blinders(blinder_seed);
+    let secret_shares = evaluate_hash_chain(share_seed, SHARES_PER_WALLET);
+    create_wallet_shares_with_randomness(
+        packed_wallet,
+        blinder,
+        private_blinder_share,
+        &secret_shares,
+    )
+}
+
+/// Reblind a packed wallet, consuming the next blinder and secret shares in
+/// the streams seeded by the wallet's current private shares
+///
+/// The blinder stream is seeded by the last private share, and the share
+/// stream by the second to last
+pub fn reblind(
+    packed_wallet: &[ScalarField],
+    private_shares: &[ScalarField],
+) -> Result<WalletShares, UtilsError> {
+    if private_shares.len() != SHARES_PER_WALLET {
+        return Err(UtilsError::InvalidShareCount(format!(
+            "expected {SHARES_PER_WALLET} private shares, got {}",
+            private_shares.len()
+        )));
+    }
+
+    derive_wallet_shares(
+        packed_wallet,
+        private_shares[SHARES_PER_WALLET - 1],
+        private_shares[SHARES_PER_WALLET - 2],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wallet::recombine_shares;
+
+    #[test]
+    fn test_hash_chain_links() {
+        let seed = ScalarField::from(42u8);
+        let chain = evaluate_hash_chain(seed, 3 /* length */);
+        assert_eq!(chain[0], _compute_poseidon_hash(&[seed]));
+        assert_eq!(chain[2], _compute_poseidon_hash(&[chain[1]]));
+    }
+
+    #[test]
+    fn test_reblind_preserves_wallet() {
+        let packed_wallet: Vec<ScalarField> = (0..SHARES_PER_WALLET as u64)
+            .map(ScalarField::from)
+            .collect();
+        let shares = derive_wallet_shares(
+            &packed_wallet,
+            ScalarField::from(1u8),
+            ScalarField::from(2u8),
+        )
+        .unwrap();
+        let reblinded = reblind(&packed_wallet, &shares.private_shares).unwrap();
+        assert_ne!(reblinded.private_shares, shares.private_shares);
+
+        // Both sharings recombine to the same wallet, up to the blinder
+        let old = recombine_shares(&shares.private_shares, &shares.blinded_public_shares).unwrap();
+        let new =
+            recombine_shares(&reblinded.private_shares, &reblinded.blinded_public_shares).unwrap();
+        assert_eq!(
+            old[..SHARES_PER_WALLET - 1],
+            packed_wallet[..SHARES_PER_WALLET - 1]
+        );
+        assert_eq!(
+            new[..SHARES_PER_WALLET - 1],
+            packed_wallet[..SHARES_PER_WALLET - 1]
+        );
+    }
+
+    #[test]
+    fn test_nullifier() {
+        let packed_wallet = vec![ScalarField::from(3u8); SHARES_PER_WALLET];
+        let (blinder, _) = derive_blinders(ScalarField::from(1u8));
+        let shares = derive_wallet_shares(
+            &packed_wallet,
+            ScalarField::from(1u8),
+            ScalarField::from(2u8),
+        )
+        .unwrap();
+        assert_eq!(shares.get_blinder(), Some(blinder));
+
+        let commitment = _compute_poseidon_hash(
+            &[
+                &[_compute_poseidon_hash(&shares.private_shares)],
+                shares.blinded_public_shares.as_slice(),
+            ]
+            .concat(),
+        );
+        assert_eq!(shares.get_share_commitment(), commitment);
+        assert_eq!(
+            shares.get_nullifier().unwrap(),
+            _compute_poseidon_hash(&[commitment, blinder])
+        );
+
+        // Reblinding the wallet moves it to a fresh nullifier
+        let reblinded = reblind(&packed_wallet, &shares.private_shares).unwrap();
+        assert_ne!(
+            reblinded.get_nullifier().unwrap(),
+            shares.get_nullifier().unwrap()
+        );
+    }
+}